@@ -1,4 +1,4 @@
-use crate::{GameSettings, UserToken};
+use crate::{GameCode, GameLanguage, GameSettings, UserToken};
 use serde::{Deserialize, Serialize};
 
 mod ws;
@@ -9,4 +9,66 @@ pub use ws::*;
 pub struct CreateGameBody {
     pub token: UserToken,
     pub settings: GameSettings,
+    /// Players other than the creator need this password to join
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Public games are listed in the lobby browser and used for quick matches
+    #[serde(default)]
+    pub public: bool,
+    /// Words are drawn from this list instead of the wordlist of the language
+    #[serde(default)]
+    pub words: Option<Vec<String>>,
+}
+
+/// A public game as listed in the lobby browser
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct LobbyInfo {
+    pub code: GameCode,
+    pub settings: GameSettings,
+    pub players: u32,
+    pub password: bool,
+    /// If the game uses a wordlist of its creator
+    #[serde(default)]
+    pub custom_words: bool,
+}
+
+/// A language the server has a wordlist for
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct LanguageInfo {
+    pub code: GameLanguage,
+    /// Name of the language in the language itself
+    pub name: String,
+}
+
+/// Finds a public game with the same mode, language and difficulty or creates one
+#[derive(Deserialize, Serialize)]
+pub struct QuickMatchBody {
+    pub token: UserToken,
+    pub settings: GameSettings,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    Player,
+    /// Receives all game updates but can't interact with the game
+    Spectator,
+}
+
+/// Query parameters of the game websocket besides the user
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct JoinQuery {
+    #[serde(default)]
+    pub role: Role,
+}
+
+/// First message a client sends on the game websocket
+///
+/// The password is sent here instead of in the query, so it doesn't end up in request logs.
+/// The server only waits for it if the game has a password.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct JoinMessage {
+    pub password: Option<String>,
 }
@@ -1,4 +1,4 @@
-use crate::{CompetitiveState, Game, Score, TeamState};
+use crate::{CompetitiveState, Game, RoundResults, TeamState, WordResult};
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
@@ -7,6 +7,71 @@ use std::fmt::Debug;
 pub enum ClientMessage {
     ChatMessage(String),
     NextRound,
+    /// Makes the player with the given nickname the new game owner, only allowed for the owner
+    TransferOwnership(String),
+    /// Removes the player with the given nickname from the game, only allowed for the owner
+    Kick(String),
+    /// Removes the player with the given nickname and doesn't let them rejoin, only allowed for
+    /// the owner
+    Ban(String),
+}
+
+/// Codes the server closes a game websocket with
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CloseCode {
+    GameNotFound = 4000,
+    GameClosed = 4001,
+    Kicked = 4002,
+    Banned = 4003,
+    GameFull = 4004,
+    PasswordRequired = 4005,
+    WrongPassword = 4006,
+    RateLimited = 4007,
+    InvalidNickname = 4008,
+    GameCrashed = 4009,
+}
+
+impl CloseCode {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            CloseCode::GameNotFound => "game not found",
+            CloseCode::GameClosed => "the game was closed",
+            CloseCode::Kicked => "you were kicked from the game",
+            CloseCode::Banned => "you are banned from this game",
+            CloseCode::GameFull => "the game is full",
+            CloseCode::PasswordRequired => "this game requires a password",
+            CloseCode::WrongPassword => "wrong password",
+            CloseCode::RateLimited => "too many messages",
+            CloseCode::InvalidNickname => "this nickname is not allowed",
+            CloseCode::GameCrashed => "the game crashed",
+        }
+    }
+}
+
+impl From<CloseCode> for u16 {
+    fn from(value: CloseCode) -> Self {
+        value as u16
+    }
+}
+
+impl TryFrom<u16> for CloseCode {
+    type Error = u16;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            4000 => Ok(CloseCode::GameNotFound),
+            4001 => Ok(CloseCode::GameClosed),
+            4002 => Ok(CloseCode::Kicked),
+            4003 => Ok(CloseCode::Banned),
+            4004 => Ok(CloseCode::GameFull),
+            4005 => Ok(CloseCode::PasswordRequired),
+            4006 => Ok(CloseCode::WrongPassword),
+            4007 => Ok(CloseCode::RateLimited),
+            4008 => Ok(CloseCode::InvalidNickname),
+            4009 => Ok(CloseCode::GameCrashed),
+            _ => Err(value),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
@@ -17,25 +82,22 @@ pub struct ChatMessage {
     pub color: ChatColor,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum ChatColor {
+    #[default]
     Neutral,
     Green,
     Red,
 }
 
-impl Default for ChatColor {
-    fn default() -> Self {
-        ChatColor::Neutral
-    }
-}
-
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type", content = "data")]
 pub enum ServerMessageInner<State> {
     UpdateGame(Game<State>),
-    Results(Vec<Score>),
+    /// Sent to a player when they solved or failed their current word
+    WordFinished(WordResult),
+    Results(RoundResults),
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -43,4 +105,22 @@ pub enum ServerMessageInner<State> {
 pub enum ServerMessage {
     Team(ServerMessageInner<TeamState>),
     Competitive(ServerMessageInner<CompetitiveState>),
+    Warning(Warning),
+    Error(ServerError),
+}
+
+/// Something the client did wrong, the connection stays open
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "type", content = "data")]
+pub enum Warning {
+    /// The last message was dropped because the client sent too many messages
+    RateLimited { retry_after_ms: u64 },
+}
+
+/// Something went wrong on the server, the game continues if possible
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "type", content = "data")]
+pub enum ServerError {
+    /// No new word could be generated, the current word or round is kept
+    WordGeneration { message: String },
 }
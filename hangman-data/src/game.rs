@@ -6,6 +6,7 @@ use serde_with::{DeserializeFromStr, SerializeDisplay};
 use std::{
     fmt::{Display, Formatter},
     num::ParseIntError,
+    ops::AddAssign,
     str::FromStr,
 };
 use thiserror::Error;
@@ -73,42 +74,20 @@ impl Display for GameMode {
     }
 }
 
-#[derive(Debug, Default, Clone, Deserialize, Serialize, Eq, PartialEq, Hash)]
-#[serde(rename_all = "snake_case")]
-pub enum GameLanguage {
-    #[default]
-    English,
-    Spanish,
-    French,
-    German,
-    Russian,
-    Turkish,
-}
+/// Code of a language from the language manifest of the server, e.g. `english`
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq, Hash)]
+#[serde(transparent)]
+pub struct GameLanguage(pub String);
 
-impl GameLanguage {
-    pub fn all() -> Vec<Self> {
-        vec![
-            Self::English,
-            Self::Spanish,
-            Self::French,
-            Self::German,
-            Self::Russian,
-            Self::Turkish,
-        ]
+impl Default for GameLanguage {
+    fn default() -> Self {
+        Self("english".to_string())
     }
 }
 
 impl Display for GameLanguage {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let lang = match self {
-            GameLanguage::English => "English",
-            GameLanguage::French => "Français",
-            GameLanguage::Spanish => "Español",
-            GameLanguage::German => "Deutsch",
-            GameLanguage::Russian => "Русский",
-            GameLanguage::Turkish => "Türkçe",
-        };
-        write!(f, "{}", lang)
+        write!(f, "{}", self.0)
     }
 }
 
@@ -153,8 +132,37 @@ pub struct GameSettings {
     pub mode: GameMode,
     pub language: GameLanguage,
     pub difficulty: Difficulty,
+    /// Number of rounds in a competitive match
+    #[serde(default = "default_rounds")]
+    pub rounds: u32,
+    /// Maximum number of players, spectators are not counted
+    #[serde(default = "default_max_players")]
+    pub max_players: u32,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::default(),
+            language: GameLanguage::default(),
+            difficulty: Difficulty::default(),
+            rounds: default_rounds(),
+            max_players: default_max_players(),
+        }
+    }
 }
 
+fn default_rounds() -> u32 {
+    1
+}
+
+fn default_max_players() -> u32 {
+    10
+}
+
+/// Number of wrong guesses after which a word is failed
+pub const MAX_TRIES: u32 = 9;
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct TeamState {
     pub chat: Vec<ChatMessage>,
@@ -171,12 +179,78 @@ pub struct CompetitiveState {
     pub countdown: chrono::DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WordOutcome {
+    Solved,
+    Failed,
+    TimeUp,
+}
+
+/// A word that a player went through
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct WordResult {
+    pub word: String,
+    pub outcome: WordOutcome,
+    pub tries_used: u32,
+}
+
+/// The points a score is made up of
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct ScoreBreakdown {
+    pub words_solved: u32,
+    pub base: u32,
+    pub length: u32,
+    pub tries: u32,
+    pub speed: u32,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> u32 {
+        self.base + self.length + self.tries + self.speed
+    }
+}
+
+impl AddAssign<&ScoreBreakdown> for ScoreBreakdown {
+    fn add_assign(&mut self, rhs: &ScoreBreakdown) {
+        self.words_solved += rhs.words_solved;
+        self.base += rhs.base;
+        self.length += rhs.length;
+        self.tries += rhs.tries;
+        self.speed += rhs.speed;
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct Score {
     pub rank: u32,
     pub nickname: String,
     pub score: u32,
+    pub breakdown: ScoreBreakdown,
+    /// Total time spent on solved words in milliseconds, used to break ties
+    pub solve_time_ms: u64,
+    pub words: Vec<WordResult>,
+}
+
+/// Results of a finished round of a match
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct RoundResults {
+    /// The round that finished, starting at 1
+    pub round: u32,
+    pub rounds: u32,
+    pub round_scores: Vec<Score>,
+    /// Cumulative standings over all finished rounds of the match
+    pub total_scores: Vec<Score>,
+}
+
+impl RoundResults {
+    pub fn match_finished(&self) -> bool {
+        self.round >= self.rounds
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
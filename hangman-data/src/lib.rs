@@ -1,6 +1,7 @@
 mod api;
 mod game;
 mod user;
+pub mod wordlist;
 
 pub use api::*;
 pub use game::*;
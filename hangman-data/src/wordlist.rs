@@ -0,0 +1,163 @@
+//! Wordlists as the preprocessor writes them and the server reads them
+//!
+//! Wordlists are tab separated, start with [`HEADER`] and are ordered by rank.
+//! Older wordlists have one word per line, the most common first.
+//! Wordlists converted from the old format have no occurrences.
+
+use serde::Deserialize;
+use thiserror::Error;
+
+/// First line of a wordlist in the current format
+pub const HEADER: &str = "rank\tword\toccurrences\tletters\tcategory";
+
+/// How upper and lower case letters are treated when guessing
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseRules {
+    /// Upper and lower case letters are the same letter
+    #[default]
+    Insensitive,
+    /// Like `Insensitive`, but `I` is the upper case letter of `ı` and `İ` of `i`
+    Turkic,
+    /// Upper and lower case letters are different letters
+    Sensitive,
+}
+
+impl CaseRules {
+    /// Form of a letter or word that is used to compare guesses
+    pub fn fold(&self, s: &str) -> String {
+        match self {
+            CaseRules::Insensitive => s.to_lowercase(),
+            CaseRules::Turkic => s.replace('I', "ı").replace('İ', "i").to_lowercase(),
+            CaseRules::Sensitive => s.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("line {line}: {reason}")]
+pub struct FormatError {
+    line: usize,
+    reason: &'static str,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Entry<'a> {
+    pub word: &'a str,
+    /// `None` in the old format and in wordlists converted from it
+    pub occurrences: Option<u64>,
+    pub category: Option<&'a str>,
+}
+
+impl Entry<'_> {
+    /// Line of the entry in the current format, `letters` is the number of graphemes
+    pub fn to_line(&self, rank: usize, letters: usize) -> String {
+        format!(
+            "{rank}\t{}\t{}\t{letters}\t{}\n",
+            self.word,
+            self.occurrences.map(|o| o.to_string()).unwrap_or_default(),
+            self.category.unwrap_or_default()
+        )
+    }
+}
+
+/// Reads a wordlist in either format, the entries are ordered by rank
+pub fn parse(content: &str) -> Result<Vec<Entry<'_>>, FormatError> {
+    let mut lines = content.lines();
+    if lines.next().map(|l| l.trim_end_matches('\r')) != Some(HEADER) {
+        return Ok(content
+            .lines()
+            .map(|word| Entry {
+                word,
+                occurrences: None,
+                category: None,
+            })
+            .collect());
+    }
+
+    let mut entries = vec![];
+    for (i, line) in lines.enumerate() {
+        let error = |reason| FormatError {
+            line: i + 2,
+            reason,
+        };
+        let [rank, word, occurrences, _letters, category] =
+            line.split('\t').collect::<Vec<_>>()[..]
+        else {
+            return Err(error("expected 5 columns"));
+        };
+        let rank: u32 = rank.parse().map_err(|_| error("invalid rank"))?;
+        let occurrences = Some(occurrences)
+            .filter(|o| !o.is_empty())
+            .map(str::parse)
+            .transpose()
+            .map_err(|_| error("invalid occurrences"))?;
+        entries.push((
+            rank,
+            Entry {
+                word,
+                occurrences,
+                category: Some(category).filter(|c| !c.is_empty()),
+            },
+        ));
+    }
+    entries.sort_by_key(|(rank, _)| *rank);
+    Ok(entries.into_iter().map(|(_, e)| e).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tsv() {
+        let content = format!("{HEADER}\n2\thouse\t900\t5\tnoun\n1\tthe\t3000\t3\t\n");
+        let entries = parse(&content).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    word: "the",
+                    occurrences: Some(3000),
+                    category: None,
+                },
+                Entry {
+                    word: "house",
+                    occurrences: Some(900),
+                    category: Some("noun"),
+                },
+            ]
+        );
+        assert_eq!(entries[1].to_line(2, 5), "2\thouse\t900\t5\tnoun\n");
+        assert_eq!(
+            parse(&format!("{HEADER}\n1\tthe\tmany\t3\t\n")).unwrap_err(),
+            FormatError {
+                line: 2,
+                reason: "invalid occurrences",
+            }
+        );
+    }
+
+    #[test]
+    fn converted_with_crlf() {
+        let content = format!("{HEADER}\r\n1\tthe\t\t3\t\r\n");
+        assert_eq!(
+            parse(&content).unwrap(),
+            vec![Entry {
+                word: "the",
+                occurrences: None,
+                category: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn legacy() {
+        let entries = parse("the\nhouse\n").unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.word).collect::<Vec<_>>(),
+            vec!["the", "house"]
+        );
+        assert!(entries.iter().all(|e| e.occurrences.is_none()));
+    }
+}
@@ -1,94 +1,313 @@
 use crate::{
-    game::{logic::GameMessage, GameManager},
+    config::RateLimitConfig,
+    game::{
+        logic::{GameMessage, PlayerMessage},
+        GameHandle, GameManager,
+    },
+    moderation, password,
+    rate_limit::{RateLimit, RateLimiter},
     sender_utils::LogSend,
+    word_generator::{self, custom::CustomWordlist, reload},
 };
 use axum::{
     extract::{
         ws::{CloseFrame, Message, WebSocket},
         Path, Query, State, WebSocketUpgrade,
     },
-    http::StatusCode,
-    response::IntoResponse,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use futures::{stream::SplitSink, SinkExt, StreamExt};
-use hangman_data::{CreateGameBody, GameCode, ServerMessage, User};
-use std::borrow::Cow;
+use hangman_data::{
+    CloseCode, CreateGameBody, GameCode, JoinMessage, JoinQuery, LanguageInfo, LobbyInfo,
+    QuickMatchBody, Role, ServerMessage, User, Warning,
+};
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
 use tokio::sync::mpsc;
-use tracing::{debug, error, trace, warn};
+use tracing::{debug, error, info, trace, warn};
 use tungstenite::Error;
 
+/// Id of the next websocket connection, see [`GameMessage::Join`]
+static NEXT_CONNECTION: AtomicU64 = AtomicU64::new(0);
+
+/// Time a client has to send its [`JoinMessage`] after connecting
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub async fn create_game(
     State(game_manager): State<GameManager>,
-    Json(CreateGameBody { token, settings }): Json<CreateGameBody>,
-) -> (StatusCode, Json<GameCode>) {
-    let code = game_manager.add_game(token, settings).await;
-    (StatusCode::CREATED, Json(code))
+    Json(CreateGameBody {
+        token,
+        settings,
+        password,
+        public,
+        words,
+    }): Json<CreateGameBody>,
+) -> Result<(StatusCode, Json<GameCode>), (StatusCode, String)> {
+    if word_generator::generator()
+        .languages()
+        .get(&settings.language)
+        .is_none()
+    {
+        return Err((StatusCode::BAD_REQUEST, "unknown language".to_string()));
+    }
+    let custom_words = match words {
+        Some(words) => Some(
+            CustomWordlist::new(words, &game_manager.config().custom_words).map_err(|e| {
+                debug!("rejecting custom wordlist: {e}");
+                (StatusCode::BAD_REQUEST, e.to_string())
+            })?,
+        ),
+        None => None,
+    };
+    let password_hash = match password.filter(|p| !p.is_empty()) {
+        Some(p) => Some(password::hash_password(p).await.map_err(|e| {
+            error!("{e}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to hash password".to_string(),
+            )
+        })?),
+        None => None,
+    };
+    let code = game_manager
+        .add_game(token, settings, public, password_hash, custom_words)
+        .await;
+    Ok((StatusCode::CREATED, Json(code)))
+}
+
+pub async fn public_games(State(game_manager): State<GameManager>) -> Json<Vec<LobbyInfo>> {
+    Json(game_manager.public_games().await)
+}
+
+pub async fn languages() -> Json<Vec<LanguageInfo>> {
+    Json(word_generator::generator().languages().infos())
+}
+
+/// Reloads the wordlists, requires the admin token as bearer token
+pub async fn reload_wordlists(
+    State(game_manager): State<GameManager>,
+    headers: HeaderMap,
+) -> (StatusCode, String) {
+    let Some(admin_token) = &game_manager.config().admin_token else {
+        return (
+            StatusCode::NOT_FOUND,
+            "no admin token configured".to_string(),
+        );
+    };
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "));
+    if token != Some(admin_token.as_str()) {
+        return (StatusCode::UNAUTHORIZED, "wrong admin token".to_string());
+    }
+    match reload::reload(game_manager.config()).await {
+        Ok(()) => (StatusCode::OK, "reloaded wordlists".to_string()),
+        Err(e) => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
+    }
+}
+
+pub async fn quickmatch(
+    State(game_manager): State<GameManager>,
+    Json(QuickMatchBody { token, settings }): Json<QuickMatchBody>,
+) -> Result<(StatusCode, Json<GameCode>), (StatusCode, String)> {
+    if word_generator::generator()
+        .languages()
+        .get(&settings.language)
+        .is_none()
+    {
+        return Err((StatusCode::BAD_REQUEST, "unknown language".to_string()));
+    }
+    match game_manager.find_match(&token, &settings).await {
+        Some(code) => {
+            debug!("quick match found game {code}");
+            Ok((StatusCode::OK, Json(code)))
+        }
+        None => {
+            let code = game_manager
+                .add_game(token, settings, true, None, None)
+                .await;
+            Ok((StatusCode::CREATED, Json(code)))
+        }
+    }
 }
 
 pub async fn game_ws(
     State(game_manager): State<GameManager>,
     Path(code): Path<GameCode>,
-    Query(user): Query<User>,
+    Query(mut user): Query<User>,
+    Query(JoinQuery { role }): Query<JoinQuery>,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
-    if let Some(game) = game_manager.get_game(code).await {
-        ws.on_upgrade(move |socket| handle_socket(socket, user, code, game))
-    } else {
-        ws.on_upgrade(move |mut socket| async move {
-            if let Err(e) = socket
-                .send(Message::Close(Some(CloseFrame {
-                    code: 4000,
-                    reason: Cow::from("game not found"),
-                })))
-                .await
-            {
-                warn!("game not found but failed to send close frame to player socket: {e}");
+    let Some(game) = game_manager.get_game(code).await else {
+        return reject_socket(ws, CloseCode::GameNotFound);
+    };
+    match moderation::validate_nickname(
+        &user.nickname,
+        &game_manager.config().moderation,
+        &game.shared.blocklist,
+    ) {
+        Ok(nickname) => user.nickname = nickname,
+        Err(e) => {
+            debug!("[{code}] rejecting nickname {}: {e}", user.nickname);
+            return reject_socket(ws, CloseCode::InvalidNickname);
+        }
+    }
+    if game.shared.is_banned(&user.token) {
+        debug!("[{code}] rejecting banned player {}", user.nickname);
+        return reject_socket(ws, CloseCode::Banned);
+    }
+    let rate_limit = game_manager.config().rate_limit;
+    ws.on_upgrade(move |mut socket| async move {
+        let password_hash = game
+            .shared
+            .password_hash
+            .as_ref()
+            .filter(|_| user.token != game.shared.creator);
+        if let Some(hash) = password_hash {
+            let Some(password) = receive_join(&mut socket).await.and_then(|j| j.password) else {
+                debug!("[{code}] {} didn't provide a password", user.nickname);
+                return close_socket(socket, CloseCode::PasswordRequired).await;
+            };
+            if !password::verify_password(hash.clone(), password).await {
+                info!("[{code}] {} provided a wrong password", user.nickname);
+                return close_socket(socket, CloseCode::WrongPassword).await;
             }
-        })
+        }
+        handle_socket(socket, user, role, code, game, rate_limit).await
+    })
+}
+
+/// Waits for the first message of a client, `None` if it isn't a [`JoinMessage`]
+async fn receive_join(socket: &mut WebSocket) -> Option<JoinMessage> {
+    let msg = tokio::time::timeout(JOIN_TIMEOUT, socket.recv())
+        .await
+        .ok()??
+        .ok()?;
+    serde_json::from_str(msg.to_text().ok()?).ok()
+}
+
+/// Clients always send a [`JoinMessage`] first, it is only read if the game has a password
+fn is_join_message(text: &str) -> bool {
+    serde_json::from_str::<JoinMessage>(text).is_ok()
+}
+
+/// Accepts the websocket connection only to close it right away
+fn reject_socket(ws: WebSocketUpgrade, close_code: CloseCode) -> Response {
+    ws.on_upgrade(move |socket| close_socket(socket, close_code))
+}
+
+async fn close_socket(mut socket: WebSocket, close_code: CloseCode) {
+    if let Err(e) = socket.send(close_message(close_code)).await {
+        warn!("failed to send close frame ({close_code:?}) to player socket: {e}");
     }
 }
 
+fn close_message(close_code: CloseCode) -> Message {
+    Message::Close(Some(CloseFrame {
+        code: close_code.into(),
+        reason: Cow::from(close_code.reason()),
+    }))
+}
+
 async fn handle_socket(
     socket: WebSocket,
     user: User,
+    role: Role,
     code: GameCode,
-    game_socket: mpsc::Sender<GameMessage>,
+    game: GameHandle,
+    rate_limit: RateLimitConfig,
 ) {
-    debug!("new ws connection by {} for game {code}", user.nickname);
+    let game_socket = game.sender;
+    debug!(
+        "new ws connection by {} for game {code} as {role:?}",
+        user.nickname
+    );
     let (sender, mut receiver) = socket.split();
 
     // Copy user token
     let token = user.token;
+    let connection = NEXT_CONNECTION.fetch_add(1, Ordering::Relaxed);
+    let leave = move || match role {
+        Role::Player => GameMessage::Leave { token, connection },
+        Role::Spectator => GameMessage::StopSpectating(token),
+    };
 
     // Join Game
     let tx = spawn_message_forwarder(sender, user.nickname.clone());
-    game_socket
-        .log_send(GameMessage::Join { user, sender: tx })
-        .await;
+    // Weak, so the connection is closed when the game drops its sender
+    let player_tx = tx.downgrade();
+    game.shared.add_connection(tx.clone());
+    let nickname = user.nickname.clone();
+    let join = match role {
+        Role::Player => GameMessage::Join {
+            user,
+            sender: tx,
+            connection,
+        },
+        Role::Spectator => GameMessage::Spectate { user, sender: tx },
+    };
+    game_socket.log_send(join).await;
 
     // Task that parses and sends client messages to the game socket
     tokio::spawn(async move {
+        let mut limiter = RateLimiter::new(&rate_limit);
         while let Some(msg) = receiver.next().await {
             match msg {
                 Ok(Message::Close(_)) => {
                     debug!("client sent closing frame");
-                    game_socket.log_send(GameMessage::Leave(token)).await;
+                    game_socket.log_send(leave()).await;
                     break;
                 }
-                Ok(msg) => match msg.to_text().map(serde_json::from_str) {
-                    Ok(Ok(message)) => {
-                        if game_socket
-                            .log_send(GameMessage::ClientMessage { token, message })
-                            .await
-                            .is_some()
-                        {
+                Ok(msg) => {
+                    match limiter.check() {
+                        RateLimit::Allowed => {}
+                        RateLimit::Dropped(retry_after) => {
+                            debug!("[{code}] dropping message by {nickname}, rate limit reached");
+                            if let Some(tx) = player_tx.upgrade() {
+                                let warning = Warning::RateLimited {
+                                    retry_after_ms: u64::try_from(retry_after.as_millis())
+                                        .unwrap_or(u64::MAX),
+                                };
+                                tx.log_send(ServerMessage::Warning(warning).into()).await;
+                            }
+                            continue;
+                        }
+                        RateLimit::Exceeded => {
+                            info!("[{code}] disconnecting {nickname}, too many messages");
+                            if let Some(tx) = player_tx.upgrade() {
+                                tx.log_send(PlayerMessage::Close(CloseCode::RateLimited))
+                                    .await;
+                            }
+                            game_socket.log_send(leave()).await;
                             break;
                         }
                     }
-                    Ok(Err(e)) => warn!("failed to parse ws message: {e}"),
-                    Err(e) => warn!("failed to parse ws message as text: {e}"),
-                },
+                    match msg.to_text().map(serde_json::from_str) {
+                        Ok(Ok(message)) if role == Role::Spectator => {
+                            debug!("ignoring message {message:?} from spectator")
+                        }
+                        Ok(Ok(message)) => {
+                            if game_socket
+                                .log_send(GameMessage::ClientMessage { token, message })
+                                .await
+                                .is_some()
+                            {
+                                break;
+                            }
+                        }
+                        Ok(Err(_)) if msg.to_text().is_ok_and(is_join_message) => {
+                            debug!("ignoring join message of a game without password")
+                        }
+                        Ok(Err(e)) => warn!("failed to parse ws message: {e}"),
+                        Err(e) => warn!("failed to parse ws message as text: {e}"),
+                    }
+                }
                 Err(e) => {
                     let b = e
                         .into_inner()
@@ -99,7 +318,7 @@ async fn handle_socket(
                     ) = *b
                     {
                         debug!("client closed connection without closing frame");
-                        game_socket.log_send(GameMessage::Leave(token)).await;
+                        game_socket.log_send(leave()).await;
                         break;
                     } else {
                         warn!("failed to receive ws message: {}", *b);
@@ -113,39 +332,40 @@ async fn handle_socket(
 fn spawn_message_forwarder(
     mut sender: SplitSink<WebSocket, Message>,
     nickname: String,
-) -> mpsc::Sender<ServerMessage> {
+) -> mpsc::Sender<PlayerMessage> {
     // Send out messages sent to the internal client socket
-    let (tx, mut rx) = mpsc::channel::<ServerMessage>(1);
+    let (tx, mut rx) = mpsc::channel::<PlayerMessage>(1);
     tokio::spawn(async move {
+        let mut close_code = CloseCode::GameClosed;
         while let Some(msg) = rx.recv().await {
             trace!("sending {msg:?} to {}", nickname);
-            match serde_json::to_string(&msg) {
-                Ok(t) => {
-                    if let Err(e) = sender.send(Message::Text(t)).await {
-                        warn!("failed to send ws message to client: {e}");
+            match msg {
+                PlayerMessage::Server(msg) => match serde_json::to_string(&msg) {
+                    Ok(t) => {
+                        if let Err(e) = sender.send(Message::Text(t)).await {
+                            warn!("failed to send ws message to client: {e}");
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        error!("failed to serialize message: {e}");
                         break;
                     }
-                }
-                Err(e) => {
-                    error!("failed to serialize message: {e}");
+                },
+                PlayerMessage::Close(code) => {
+                    close_code = code;
                     break;
                 }
             }
         }
-        if let Err(e) = sender
-            .send(Message::Close(Some(CloseFrame {
-                code: 4001,
-                reason: Cow::from("the game was closed"),
-            })))
-            .await
-        {
+        if let Err(e) = sender.send(close_message(close_code)).await {
             let b = e
                 .into_inner()
                 .downcast::<Error>()
                 .expect("failed to downcast axum error to tungstenite error");
             if !matches!(*b, Error::ConnectionClosed) {
                 // connection wasn't closed normally
-                warn!("game ended but failed to send close frame to player socket: {b}");
+                warn!("failed to send close frame ({close_code:?}) to player socket: {b}");
             }
         }
     });
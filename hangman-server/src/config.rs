@@ -1,13 +1,112 @@
 use config::Config;
 use serde::Deserialize;
 use std::net::IpAddr;
+use thiserror::Error;
 
-#[derive(Deserialize)]
+#[derive(Debug, Error, PartialEq)]
+pub enum InvalidConfig {
+    #[error("{0} must be greater than 0")]
+    NotPositive(&'static str),
+}
+
+#[derive(Debug, Deserialize)]
 pub struct HangmanConfig {
     pub address: IpAddr,
     pub port: u16,
     pub public_dir: String,
     pub wordlists_dir: String,
+    /// Reload the wordlists when files in `wordlists_dir` change
+    pub watch_wordlists: bool,
+    /// Token for the admin endpoints, they are disabled without it
+    pub admin_token: Option<String>,
+    /// Time a disconnected player has to reconnect before they leave the game
+    pub reconnect_grace_secs: u64,
+    pub scoring: ScoringConfig,
+    pub limits: GameLimitsConfig,
+    pub rate_limit: RateLimitConfig,
+    pub moderation: ModerationConfig,
+    pub custom_words: CustomWordsConfig,
+}
+
+/// Points awarded for solved words in competitive mode
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct ScoringConfig {
+    /// Points for every solved word
+    pub base_points: u32,
+    /// Points for every letter of a solved word
+    pub points_per_letter: u32,
+    /// Points for every try that was left when the word was solved
+    pub points_per_try_left: u32,
+    /// Maximum bonus for solving a word fast, decreases linearly to 0 over `speed_bonus_secs`
+    pub speed_bonus: u32,
+    pub speed_bonus_secs: u64,
+}
+
+/// When games are closed by the server
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct GameLimitsConfig {
+    /// Games that nobody joined are closed after this time
+    pub unjoined_timeout_secs: u64,
+    /// Games without any activity are closed after this time
+    pub idle_timeout_secs: u64,
+    /// Games are always closed after this time
+    pub max_lifetime_secs: u64,
+    /// How often games are checked
+    pub reap_interval_secs: u64,
+    /// Upper bound for the player limit of every game
+    pub max_players: u32,
+}
+
+impl GameLimitsConfig {
+    fn validate(&self) -> Result<(), InvalidConfig> {
+        if self.reap_interval_secs == 0 {
+            return Err(InvalidConfig::NotPositive("limits.reap_interval_secs"));
+        }
+        Ok(())
+    }
+}
+
+/// Limits how many messages a single connection can send
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct RateLimitConfig {
+    /// Messages per second a connection can send on average
+    pub refill_per_sec: f64,
+    /// Messages a connection can send at once
+    pub burst: u32,
+    /// Connections are closed when more messages are dropped within `dropped_window_secs`
+    pub max_dropped: u32,
+    pub dropped_window_secs: u64,
+}
+
+impl RateLimitConfig {
+    fn validate(&self) -> Result<(), InvalidConfig> {
+        if self.refill_per_sec.is_nan() || self.refill_per_sec <= 0.0 {
+            return Err(InvalidConfig::NotPositive("rate_limit.refill_per_sec"));
+        }
+        if self.burst == 0 {
+            return Err(InvalidConfig::NotPositive("rate_limit.burst"));
+        }
+        Ok(())
+    }
+}
+
+/// Validation of nicknames and filtering of chat messages
+#[derive(Clone, Debug, Deserialize)]
+pub struct ModerationConfig {
+    pub nickname_min_len: usize,
+    pub nickname_max_len: usize,
+    /// Directory with a list of blocked words for every language
+    pub blocklists_dir: String,
+}
+
+/// Limits for wordlists that are supplied by game creators
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct CustomWordsConfig {
+    /// Minimum number of different words
+    pub min_words: usize,
+    pub max_words: usize,
+    /// Maximum number of letters of a word
+    pub max_word_len: usize,
 }
 
 pub fn load_config() -> HangmanConfig {
@@ -20,9 +119,104 @@ pub fn load_config() -> HangmanConfig {
         .unwrap()
         .set_default("wordlists_dir", "wordlists")
         .unwrap()
+        .set_default("watch_wordlists", true)
+        .unwrap()
+        .set_default("reconnect_grace_secs", 10)
+        .unwrap()
+        .set_default("scoring.base_points", 10)
+        .unwrap()
+        .set_default("scoring.points_per_letter", 1)
+        .unwrap()
+        .set_default("scoring.points_per_try_left", 2)
+        .unwrap()
+        .set_default("scoring.speed_bonus", 10)
+        .unwrap()
+        .set_default("scoring.speed_bonus_secs", 30)
+        .unwrap()
+        .set_default("limits.unjoined_timeout_secs", 5 * 60)
+        .unwrap()
+        .set_default("limits.idle_timeout_secs", 30 * 60)
+        .unwrap()
+        .set_default("limits.max_lifetime_secs", 12 * 60 * 60)
+        .unwrap()
+        .set_default("limits.reap_interval_secs", 60)
+        .unwrap()
+        .set_default("limits.max_players", 50)
+        .unwrap()
+        .set_default("rate_limit.refill_per_sec", 4.0)
+        .unwrap()
+        .set_default("rate_limit.burst", 8)
+        .unwrap()
+        .set_default("rate_limit.max_dropped", 40)
+        .unwrap()
+        .set_default("rate_limit.dropped_window_secs", 10)
+        .unwrap()
+        .set_default("moderation.nickname_min_len", 1)
+        .unwrap()
+        .set_default("moderation.nickname_max_len", 20)
+        .unwrap()
+        .set_default("moderation.blocklists_dir", "blocklists")
+        .unwrap()
+        .set_default("custom_words.min_words", 3)
+        .unwrap()
+        .set_default("custom_words.max_words", 2000)
+        .unwrap()
+        .set_default("custom_words.max_word_len", 32)
+        .unwrap()
         .build()
         .expect("failed to read config");
-    config
+    let config: HangmanConfig = config
         .try_deserialize()
-        .expect("failed to deserialize config")
+        .expect("failed to deserialize config");
+    if let Err(e) = config
+        .limits
+        .validate()
+        .and_then(|_| config.rate_limit.validate())
+    {
+        panic!("invalid config: {e}");
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_reap_interval() {
+        let limits = GameLimitsConfig {
+            unjoined_timeout_secs: 60,
+            idle_timeout_secs: 60,
+            max_lifetime_secs: 60,
+            reap_interval_secs: 0,
+            max_players: 10,
+        };
+        assert_eq!(
+            limits.validate(),
+            Err(InvalidConfig::NotPositive("limits.reap_interval_secs"))
+        );
+    }
+
+    #[test]
+    fn zero_rate_limit() {
+        let rate_limit = RateLimitConfig {
+            refill_per_sec: 0.0,
+            burst: 8,
+            max_dropped: 40,
+            dropped_window_secs: 10,
+        };
+        assert_eq!(
+            rate_limit.validate(),
+            Err(InvalidConfig::NotPositive("rate_limit.refill_per_sec"))
+        );
+        assert_eq!(
+            RateLimitConfig {
+                refill_per_sec: 4.0,
+                burst: 0,
+                ..rate_limit
+            }
+            .validate(),
+            Err(InvalidConfig::NotPositive("rate_limit.burst"))
+        );
+    }
 }
@@ -1,45 +1,389 @@
-use crate::game::logic::GameMessage;
-use hangman_data::{GameCode, GameMode, GameSettings, UserToken};
-use std::{collections::HashMap, sync::Arc};
-use tokio::sync::{mpsc, Mutex};
-use tracing::{debug, info};
+use crate::{
+    config::{GameLimitsConfig, HangmanConfig},
+    game::logic::{GameMessage, PlayerMessage},
+    moderation::Blocklist,
+    sender_utils::LogSend,
+    word_generator::{self, custom::CustomWordlist},
+};
+use hangman_data::{CloseCode, GameCode, GameMode, GameSettings, LobbyInfo, UserToken};
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{mpsc, Mutex},
+    task::JoinHandle,
+};
+use tracing::{debug, error, info};
 
 pub mod logic;
 
+/// Handle to a running game
+#[derive(Clone, Debug)]
+pub struct GameHandle {
+    pub sender: mpsc::Sender<GameMessage>,
+    pub shared: Arc<SharedGame>,
+}
+
+/// State of a game that is shared between the game loop and the rest of the server
+#[derive(Debug)]
+pub struct SharedGame {
+    /// The player that created the game, they don't need the password
+    pub creator: UserToken,
+    pub settings: GameSettings,
+    pub public: bool,
+    pub password_hash: Option<String>,
+    /// Blocked words of the game language
+    pub blocklist: Arc<Blocklist>,
+    pub custom_words: Option<CustomWordlist>,
+    player_count: AtomicU32,
+    banned: std::sync::Mutex<HashSet<UserToken>>,
+    created: Instant,
+    /// When the game loop received the last message, `None` if it never received one
+    last_activity: std::sync::Mutex<Option<Instant>>,
+    /// Sockets of all players and spectators that the game loop didn't drop yet
+    ///
+    /// A panicking game loop drops its senders while unwinding, these stay open until the
+    /// connections are closed with the right code.
+    connections: std::sync::Mutex<Vec<mpsc::Sender<PlayerMessage>>>,
+}
+
+impl SharedGame {
+    fn new(
+        creator: UserToken,
+        settings: GameSettings,
+        public: bool,
+        password_hash: Option<String>,
+        blocklist: Arc<Blocklist>,
+        custom_words: Option<CustomWordlist>,
+    ) -> Self {
+        Self {
+            creator,
+            settings,
+            public,
+            password_hash,
+            blocklist,
+            custom_words,
+            player_count: AtomicU32::new(0),
+            banned: std::sync::Mutex::default(),
+            created: Instant::now(),
+            last_activity: std::sync::Mutex::new(None),
+            connections: std::sync::Mutex::default(),
+        }
+    }
+
+    pub fn ban(&self, token: UserToken) {
+        self.banned
+            .lock()
+            .expect("failed to lock banned tokens")
+            .insert(token);
+    }
+
+    pub fn is_banned(&self, token: &UserToken) -> bool {
+        self.banned
+            .lock()
+            .expect("failed to lock banned tokens")
+            .contains(token)
+    }
+
+    pub fn player_count(&self) -> u32 {
+        self.player_count.load(Ordering::Relaxed)
+    }
+
+    pub fn set_player_count(&self, count: usize) {
+        self.player_count.store(count as u32, Ordering::Relaxed);
+    }
+
+    /// If the player can join without a password
+    fn is_open_for(&self, token: &UserToken) -> bool {
+        self.public
+            && self.password_hash.is_none()
+            && self.player_count() < self.settings.max_players
+            && !self.is_banned(token)
+    }
+
+    fn lobby_info(&self, code: GameCode) -> LobbyInfo {
+        LobbyInfo {
+            code,
+            settings: self.settings.clone(),
+            players: self.player_count(),
+            password: self.password_hash.is_some(),
+            custom_words: self.custom_words.is_some(),
+        }
+    }
+
+    pub fn touch(&self) {
+        *self
+            .last_activity
+            .lock()
+            .expect("failed to lock last activity") = Some(Instant::now());
+    }
+
+    pub fn add_connection(&self, sender: mpsc::Sender<PlayerMessage>) {
+        let mut connections = self.connections.lock().expect("failed to lock connections");
+        connections.retain(|c| !c.is_closed());
+        connections.push(sender);
+    }
+
+    /// Forgets a connection, it's closed once the game loop drops its sender as well
+    pub fn remove_connection(&self, sender: &mpsc::Sender<PlayerMessage>) {
+        self.connections
+            .lock()
+            .expect("failed to lock connections")
+            .retain(|c| !c.same_channel(sender));
+    }
+
+    /// Closes all sockets that are still open
+    async fn close_connections(&self, close_code: CloseCode) {
+        let senders: Vec<_> = self
+            .connections
+            .lock()
+            .expect("failed to lock connections")
+            .drain(..)
+            .filter(|c| !c.is_closed())
+            .collect();
+        for sender in senders {
+            sender.log_send(PlayerMessage::Close(close_code)).await;
+        }
+    }
+
+    /// Why the game should be closed, if it should be closed
+    fn reap_reason(&self, limits: &GameLimitsConfig) -> Option<&'static str> {
+        if self.created.elapsed() > Duration::from_secs(limits.max_lifetime_secs) {
+            return Some("reached the maximum lifetime");
+        }
+        match *self
+            .last_activity
+            .lock()
+            .expect("failed to lock last activity")
+        {
+            None if self.created.elapsed() > Duration::from_secs(limits.unjoined_timeout_secs) => {
+                Some("nobody joined")
+            }
+            Some(t) if t.elapsed() > Duration::from_secs(limits.idle_timeout_secs) => Some("idle"),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct GameManager {
-    games: Arc<Mutex<HashMap<GameCode, mpsc::Sender<GameMessage>>>>,
+    games: Arc<Mutex<HashMap<GameCode, GameHandle>>>,
+    config: Arc<HangmanConfig>,
 }
 
 impl GameManager {
-    pub fn new() -> Self {
+    pub fn new(config: Arc<HangmanConfig>) -> Self {
         Self {
             games: Arc::new(Mutex::new(HashMap::new())),
+            config,
         }
     }
 }
 
 impl GameManager {
-    pub async fn add_game(&self, owner: UserToken, settings: GameSettings) -> GameCode {
+    pub async fn add_game(
+        &self,
+        owner: UserToken,
+        mut settings: GameSettings,
+        public: bool,
+        password_hash: Option<String>,
+        custom_words: Option<CustomWordlist>,
+    ) -> GameCode {
+        settings.max_players = settings
+            .max_players
+            .clamp(1, self.config.limits.max_players.max(1));
         let code = GameCode::random();
         info!("new game: {}", code);
         let (tx, rx) = mpsc::channel(10);
         let games = Arc::clone(&self.games);
-        tokio::spawn(async move {
-            match &settings.mode {
-                GameMode::Team => logic::team::game_loop(rx, code, settings, owner).await,
+        let config = Arc::clone(&self.config);
+        let weak_tx = tx.downgrade();
+        let shared = Arc::new(SharedGame::new(
+            owner,
+            settings.clone(),
+            public,
+            password_hash,
+            word_generator::generator().blocklist(&settings.language),
+            custom_words,
+        ));
+        let handle = GameHandle {
+            sender: tx,
+            shared: Arc::clone(&shared),
+        };
+        // Inserted before the loop starts, it might end right away if no word can be generated
+        self.games.lock().await.insert(code, handle);
+        let mode = settings.mode.clone();
+        let loop_shared = Arc::clone(&shared);
+        let game_loop = async move {
+            match mode {
+                GameMode::Team => {
+                    logic::team::game_loop(rx, weak_tx, code, settings, owner, config, loop_shared)
+                        .await
+                }
                 GameMode::Competitive => {
-                    logic::competitive::game_loop(rx, code, settings, owner).await
+                    logic::competitive::game_loop(
+                        rx,
+                        weak_tx,
+                        code,
+                        settings,
+                        owner,
+                        config,
+                        loop_shared,
+                    )
+                    .await
                 }
             }
-            debug!("[{code}] game loop finished, removing game");
+        };
+        // The game is removed even if the loop panics
+        let game_loop = tokio::spawn(game_loop);
+        tokio::spawn(async move {
+            run_supervised(code, game_loop, &shared).await;
             games.lock().await.remove(&code);
         });
-        self.games.lock().await.insert(code, tx);
         code
     }
 
-    pub async fn get_game(&self, code: GameCode) -> Option<mpsc::Sender<GameMessage>> {
-        self.games.lock().await.get(&code).map(mpsc::Sender::clone)
+    pub fn config(&self) -> &HangmanConfig {
+        &self.config
+    }
+
+    pub async fn get_game(&self, code: GameCode) -> Option<GameHandle> {
+        self.games.lock().await.get(&code).cloned()
+    }
+
+    /// All public games, the fullest first
+    pub async fn public_games(&self) -> Vec<LobbyInfo> {
+        let mut games: Vec<_> = self
+            .games
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, game)| game.shared.public)
+            .map(|(code, game)| game.shared.lobby_info(*code))
+            .collect();
+        games.sort_by_key(|g| std::cmp::Reverse(g.players));
+        games
+    }
+
+    /// The fullest public game with the same mode, language and difficulty the player can join
+    ///
+    /// Games with a custom wordlist are never matched.
+    pub async fn find_match(&self, token: &UserToken, settings: &GameSettings) -> Option<GameCode> {
+        self.games
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, game)| {
+                let s = &game.shared.settings;
+                game.shared.is_open_for(token)
+                    && game.shared.custom_words.is_none()
+                    && s.mode == settings.mode
+                    && s.language == settings.language
+                    && s.difficulty == settings.difficulty
+            })
+            .max_by_key(|(_, game)| game.shared.player_count())
+            .map(|(code, _)| *code)
+    }
+
+    /// Periodically closes games that are idle or too old
+    pub fn spawn_reaper(&self) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(
+                manager.config.limits.reap_interval_secs,
+            ));
+            loop {
+                interval.tick().await;
+                manager.reap_games().await;
+            }
+        });
+    }
+
+    async fn reap_games(&self) {
+        // Don't hold the lock while sending, the game removes itself from the map when it's closed
+        let games: Vec<_> = self
+            .games
+            .lock()
+            .await
+            .iter()
+            .map(|(code, game)| (*code, game.clone()))
+            .collect();
+        for (code, game) in games {
+            if let Some(reason) = game.shared.reap_reason(&self.config.limits) {
+                info!("[{code}] closing game: {reason}");
+                game.sender.log_send(GameMessage::Shutdown).await;
+            }
+        }
+    }
+}
+
+/// Waits for a spawned game loop and closes the remaining connections when it ends
+///
+/// If the loop panicked, they are closed with [`CloseCode::GameCrashed`].
+async fn run_supervised(code: GameCode, game_loop: JoinHandle<()>, shared: &SharedGame) {
+    match game_loop.await {
+        Ok(()) => {
+            debug!("[{code}] game loop finished, removing game");
+            shared.close_connections(CloseCode::GameClosed).await;
+        }
+        Err(e) if e.is_panic() => {
+            error!(
+                "[{code}] game loop panicked, removing game: {}",
+                panic_message(e.into_panic())
+            );
+            shared.close_connections(CloseCode::GameCrashed).await;
+        }
+        Err(e) => {
+            error!("[{code}] game loop was cancelled, removing game: {e}");
+            shared.close_connections(CloseCode::GameCrashed).await;
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(s) => *s,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(s) => s.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn crashed_game_closes_connections() {
+        let shared = SharedGame::new(
+            UserToken::random(),
+            GameSettings::default(),
+            false,
+            None,
+            Arc::default(),
+            None,
+        );
+        let (tx, mut rx) = mpsc::channel(1);
+        shared.add_connection(tx.clone());
+        let game_loop = async move {
+            // The game loop owns the only strong sender, like `Players` does
+            let _tx = tx;
+            tokio::task::yield_now().await;
+            panic!("game loop crashed");
+        };
+
+        run_supervised(GameCode::random(), tokio::spawn(game_loop), &shared).await;
+        assert!(matches!(
+            rx.recv().await,
+            Some(PlayerMessage::Close(CloseCode::GameCrashed))
+        ));
+        assert!(rx.recv().await.is_none());
     }
 }
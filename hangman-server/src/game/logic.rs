@@ -1,29 +1,74 @@
 //! Game logic
 
-use crate::sender_utils::send_to_all;
-use hangman_data::{ChatMessage, ClientMessage, ServerMessage, User, UserToken};
+use crate::{
+    game::SharedGame,
+    moderation,
+    sender_utils::{send_to_all, LogSend},
+    word_generator::GeneratorError,
+};
+use hangman_data::{
+    ChatMessage, ClientMessage, CloseCode, ServerError, ServerMessage, User, UserToken,
+};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Debug,
-    ops::{Deref, DerefMut},
+    ops::Deref,
+    sync::Arc,
+    time::Duration,
 };
 use tokio::sync::mpsc;
+use unicode_segmentation::UnicodeSegmentation;
 
 pub mod competitive;
+mod scoring;
 pub mod team;
 mod word;
+mod word_queue;
 
 #[derive(Debug)]
 pub enum GameMessage {
     Join {
         user: User,
-        sender: mpsc::Sender<ServerMessage>,
+        sender: mpsc::Sender<PlayerMessage>,
+        /// Tells connections of the same player apart
+        connection: u64,
+    },
+    /// The connection of a player was closed
+    Leave {
+        token: UserToken,
+        connection: u64,
+    },
+    /// Watches the game without playing
+    Spectate {
+        user: User,
+        sender: mpsc::Sender<PlayerMessage>,
     },
-    Leave(UserToken),
+    StopSpectating(UserToken),
     ClientMessage {
         token: UserToken,
         message: ClientMessage,
     },
+    /// A disconnected player didn't reconnect in time, they leave the game now
+    ReconnectGraceExpired {
+        token: UserToken,
+        session: u64,
+    },
+    /// Closes the game and the connections of all players and spectators
+    Shutdown,
+}
+
+/// Message that is forwarded to the websocket of a player
+#[derive(Clone, Debug)]
+pub enum PlayerMessage {
+    Server(ServerMessage),
+    /// Closes the connection with the given code
+    Close(CloseCode),
+}
+
+impl From<ServerMessage> for PlayerMessage {
+    fn from(value: ServerMessage) -> Self {
+        Self::Server(value)
+    }
 }
 
 pub fn join_message(name: &str) -> ChatMessage {
@@ -40,27 +85,254 @@ pub fn leave_message(name: &str) -> ChatMessage {
     }
 }
 
+pub fn owner_message(name: &str) -> ChatMessage {
+    ChatMessage {
+        content: format!("★ {} is now the game owner", name),
+        ..Default::default()
+    }
+}
+
+pub fn kick_message(name: &str, banned: bool) -> ChatMessage {
+    ChatMessage {
+        content: format!(
+            "← {} was {} from the game",
+            name,
+            if banned { "banned" } else { "kicked" }
+        ),
+        ..Default::default()
+    }
+}
+
+/// Tells players that no new word could be generated
+pub fn word_error(error: &GeneratorError) -> ServerMessage {
+    ServerMessage::Error(ServerError::WordGeneration {
+        message: error.to_string(),
+    })
+}
+
 #[derive(Debug)]
-pub struct Players(HashMap<UserToken, (mpsc::Sender<ServerMessage>, User)>);
+pub struct Players {
+    players: HashMap<UserToken, (mpsc::Sender<PlayerMessage>, User)>,
+    /// Current connection of every player
+    connections: HashMap<UserToken, u64>,
+    /// Tokens of all players in the order they joined
+    join_order: Vec<UserToken>,
+    /// Players whose connection was closed, with the session that ended
+    disconnected: HashMap<UserToken, u64>,
+    /// Counter to tell sessions of the same player apart
+    last_session: u64,
+    game: mpsc::WeakSender<GameMessage>,
+    reconnect_grace: Duration,
+    shared: Arc<SharedGame>,
+    /// Connections that only receive game updates, they are not part of the game
+    spectators: HashMap<UserToken, (mpsc::Sender<PlayerMessage>, User)>,
+}
 
 impl Players {
-    pub fn new() -> Self {
-        Self(HashMap::new())
+    pub fn new(
+        game: mpsc::WeakSender<GameMessage>,
+        reconnect_grace: Duration,
+        shared: Arc<SharedGame>,
+    ) -> Self {
+        Self {
+            players: HashMap::new(),
+            connections: HashMap::new(),
+            join_order: vec![],
+            disconnected: HashMap::new(),
+            last_session: 0,
+            game,
+            reconnect_grace,
+            shared,
+            spectators: HashMap::new(),
+        }
+    }
+
+    /// Appends a number to the nickname if another player has a nickname that looks the same
+    ///
+    /// The nickname is shortened so it doesn't get longer than `max_len` with the number,
+    /// but at least its first letter is kept.
+    pub fn with_unique_nickname(&self, user: User, max_len: usize) -> User {
+        let taken: HashSet<_> = self
+            .players
+            .values()
+            .filter(|(_, u)| u.token != user.token)
+            .map(|(_, u)| moderation::comparable(&u.nickname))
+            .collect();
+        if !taken.contains(&moderation::comparable(&user.nickname)) {
+            return user;
+        }
+        let nickname = (2..)
+            .map(|n| {
+                let suffix = format!(" ({n})");
+                let base: String = user
+                    .nickname
+                    .graphemes(true)
+                    .take(max_len.saturating_sub(suffix.chars().count()).max(1))
+                    .collect();
+                format!("{}{suffix}", base.trim_end())
+            })
+            .find(|n| !taken.contains(&moderation::comparable(n)))
+            .expect("no free nickname");
+        User { nickname, ..user }
     }
 
-    pub async fn add_player(&mut self, tx: mpsc::Sender<ServerMessage>, user: User) {
-        self.insert(user.token, (tx, user));
+    /// Adds a player, returns `true` if the player was already in the game and only reconnected
+    pub async fn add_player(
+        &mut self,
+        tx: mpsc::Sender<PlayerMessage>,
+        user: User,
+        connection: u64,
+    ) -> bool {
+        self.disconnected.remove(&user.token);
+        self.connections.insert(user.token, connection);
+        let resumed = self.players.contains_key(&user.token);
+        if !resumed {
+            self.join_order.push(user.token);
+        }
+        if let Some((old, _)) = self.players.insert(user.token, (tx, user)) {
+            self.shared.remove_connection(&old);
+        }
+        self.shared.set_player_count(self.players.len());
+        resumed
+    }
+
+    /// Starts the reconnect grace period for a player whose connection was closed
+    ///
+    /// Returns `None` if there is no player with this token or the player already reconnected
+    /// with another connection.
+    pub fn disconnect_player(&mut self, token: UserToken, connection: u64) -> Option<&User> {
+        if self.connections.get(&token) != Some(&connection) {
+            return None;
+        }
+        self.last_session += 1;
+        let session = self.last_session;
+        self.disconnected.insert(token, session);
+
+        let game = self.game.clone();
+        let grace = self.reconnect_grace;
+        tokio::spawn(async move {
+            tokio::time::sleep(grace).await;
+            if let Some(game) = game.upgrade() {
+                game.log_send(GameMessage::ReconnectGraceExpired { token, session })
+                    .await;
+            }
+        });
+        self.players.get(&token).map(|(_, u)| u)
+    }
+
+    /// Removes a player if they didn't reconnect since the given session ended
+    pub async fn expire_session(&mut self, token: &UserToken, session: u64) -> Option<User> {
+        if self.disconnected.get(token) != Some(&session) {
+            return None;
+        }
+        self.remove_player(token).await.map(|(_, u)| u)
     }
 
     pub async fn remove_player(
         &mut self,
         token: &UserToken,
-    ) -> Option<(mpsc::Sender<ServerMessage>, User)> {
-        self.remove(token)
+    ) -> Option<(mpsc::Sender<PlayerMessage>, User)> {
+        self.join_order.retain(|t| t != token);
+        self.disconnected.remove(token);
+        self.connections.remove(token);
+        let removed = self.players.remove(token);
+        if let Some((sender, _)) = &removed {
+            self.shared.remove_connection(sender);
+        }
+        self.shared.set_player_count(self.players.len());
+        removed
+    }
+
+    /// Removes a player and closes their connection with the given code
+    pub async fn kick_player(&mut self, token: &UserToken, close_code: CloseCode) -> Option<User> {
+        let (sender, user) = self.remove_player(token).await?;
+        sender.log_send(PlayerMessage::Close(close_code)).await;
+        Some(user)
+    }
+
+    pub fn add_spectator(&mut self, tx: mpsc::Sender<PlayerMessage>, user: User) {
+        if let Some((old, _)) = self.spectators.insert(user.token, (tx, user)) {
+            self.shared.remove_connection(&old);
+        }
+    }
+
+    pub fn remove_spectator(
+        &mut self,
+        token: &UserToken,
+    ) -> Option<(mpsc::Sender<PlayerMessage>, User)> {
+        let removed = self.spectators.remove(token);
+        if let Some((sender, _)) = &removed {
+            self.shared.remove_connection(sender);
+        }
+        removed
+    }
+
+    /// If there is no room for the given player, players that are already in the game always fit
+    pub fn is_full(&self, token: &UserToken, max_players: u32) -> bool {
+        !self.players.contains_key(token) && self.players.len() >= max_players as usize
+    }
+
+    /// The connected player that is in the game for the longest time
+    pub fn longest_present(&self) -> Option<&User> {
+        self.join_order
+            .iter()
+            .find(|t| !self.disconnected.contains_key(t))
+            .and_then(|t| self.players.get(t))
+            .map(|(_, u)| u)
     }
 
+    /// The player that takes over when the owner leaves
+    ///
+    /// Falls back to the player that joined first if all players are disconnected,
+    /// so the owner never stays a player that left the game.
+    pub fn next_owner(&self) -> Option<&User> {
+        self.longest_present().or_else(|| {
+            self.join_order
+                .first()
+                .and_then(|t| self.players.get(t))
+                .map(|(_, u)| u)
+        })
+    }
+
+    pub fn find_by_nickname(&self, nickname: &str) -> Option<&User> {
+        self.players
+            .values()
+            .map(|(_, u)| u)
+            .find(|u| u.nickname == nickname)
+    }
+
+    /// Sends a message to all players and spectators
     pub async fn send_to_all(&self, msg: ServerMessage) {
-        send_to_all(self.iter().map(|(_, (s, _))| s), msg).await;
+        send_to_all(
+            self.values()
+                .chain(self.spectators.values())
+                .map(|(s, _)| s),
+            PlayerMessage::from(msg),
+        )
+        .await;
+    }
+
+    pub async fn send_to_players(&self, msg: ServerMessage) {
+        send_to_all(self.values().map(|(s, _)| s), PlayerMessage::from(msg)).await;
+    }
+
+    pub async fn send_to_spectators(&self, msg: ServerMessage) {
+        send_to_all(
+            self.spectators.values().map(|(s, _)| s),
+            PlayerMessage::from(msg),
+        )
+        .await;
+    }
+
+    /// Closes the connections of all players and spectators
+    pub async fn close_all(&self, close_code: CloseCode) {
+        send_to_all(
+            self.values()
+                .chain(self.spectators.values())
+                .map(|(s, _)| s),
+            PlayerMessage::Close(close_code),
+        )
+        .await;
     }
 
     pub fn player_names(&self) -> Vec<String> {
@@ -69,15 +341,55 @@ impl Players {
 }
 
 impl Deref for Players {
-    type Target = HashMap<UserToken, (mpsc::Sender<ServerMessage>, User)>;
+    type Target = HashMap<UserToken, (mpsc::Sender<PlayerMessage>, User)>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.players
     }
 }
 
-impl DerefMut for Players {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hangman_data::GameSettings;
+
+    fn players() -> Players {
+        let shared = Arc::new(SharedGame::new(
+            UserToken::random(),
+            GameSettings::default(),
+            false,
+            None,
+            Arc::default(),
+            None,
+        ));
+        let (game, _) = mpsc::channel(1);
+        Players::new(game.downgrade(), Duration::from_secs(60), shared)
+    }
+
+    #[tokio::test]
+    async fn leave_of_replaced_connection_is_ignored() {
+        let mut players = players();
+        let user = User::new("player");
+        let (old, _old_rx) = mpsc::channel(1);
+        let (new, _new_rx) = mpsc::channel(1);
+        assert!(!players.add_player(old, user.clone(), 1).await);
+        assert!(players.add_player(new, user.clone(), 2).await);
+
+        assert!(players.disconnect_player(user.token, 1).is_none());
+        assert_eq!(players.longest_present(), Some(&user));
+        assert!(players.disconnect_player(user.token, 2).is_some());
+        assert_eq!(players.longest_present(), None);
+        assert_eq!(players.next_owner(), Some(&user));
+    }
+
+    #[tokio::test]
+    async fn unique_nickname_within_max_len() {
+        let mut players = players();
+        let (tx, _rx) = mpsc::channel(1);
+        players.add_player(tx, User::new("hangman"), 1).await;
+        let user = players.with_unique_nickname(User::new("hangman"), 8);
+        assert_eq!(user.nickname, "hang (2)");
+        let user = players.with_unique_nickname(User::new("hangman"), 3);
+        assert_eq!(user.nickname, "h (2)");
     }
 }
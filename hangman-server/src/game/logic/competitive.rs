@@ -1,23 +1,34 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use chrono::Utc;
 use once_cell::sync::Lazy;
 use tokio::sync::{mpsc, Mutex, RwLock};
-use tracing::{debug, info, warn};
+use tracing::{debug, error, info, warn};
 
 use hangman_data::{
-    ChatColor, ChatMessage, ClientMessage, CompetitiveState, Game, GameCode, GameSettings, Score,
-    ServerMessage, ServerMessageInner, User, UserToken,
+    ChatColor, ChatMessage, ClientMessage, CloseCode, CompetitiveState, Game, GameCode,
+    GameSettings, RoundResults, Score, ScoreBreakdown, ServerMessage, ServerMessageInner,
+    UserToken, WordOutcome, WordResult, MAX_TRIES,
 };
 
 use crate::{
-    game::logic::{
-        join_message, leave_message,
-        word::{GuessResult, Word},
-        GameMessage, Players,
+    config::HangmanConfig,
+    game::{
+        logic::{
+            join_message, kick_message, leave_message, owner_message, scoring,
+            word::{GuessResult, Word},
+            word_error,
+            word_queue::WordQueue,
+            GameMessage, PlayerMessage, Players,
+        },
+        SharedGame,
     },
     sender_utils::LogSend,
-    word_generator,
+    word_generator::GeneratorError,
 };
 
 static GAME_DURATION: Lazy<chrono::Duration> = Lazy::new(|| chrono::Duration::minutes(3));
@@ -28,10 +39,40 @@ struct PlayerState {
     pub countdown: chrono::DateTime<Utc>,
     pub word: Word,
     pub word_index: usize,
-    pub score: u32,
+    /// When the player got their current word
+    pub word_started: Instant,
+    pub breakdown: ScoreBreakdown,
+    /// Time spent on solved words
+    pub solve_time: Duration,
+    /// All words this player finished in the current round
+    pub words: Vec<WordResult>,
+    pub total: MatchTotal,
+}
+
+/// What a player achieved over all finished rounds of the current match
+#[derive(Default)]
+struct MatchTotal {
+    pub breakdown: ScoreBreakdown,
+    pub solve_time: Duration,
+    pub words: Vec<WordResult>,
 }
 
 impl PlayerState {
+    fn new(chat: Vec<ChatMessage>, countdown: chrono::DateTime<Utc>, word: Word) -> Self {
+        Self {
+            tries_used: 0,
+            chat,
+            countdown,
+            word,
+            word_index: 0,
+            word_started: Instant::now(),
+            breakdown: ScoreBreakdown::default(),
+            solve_time: Duration::ZERO,
+            words: vec![],
+            total: MatchTotal::default(),
+        }
+    }
+
     fn to_state(&self) -> CompetitiveState {
         CompetitiveState {
             chat: self.chat.clone(),
@@ -40,13 +81,90 @@ impl PlayerState {
             countdown: self.countdown,
         }
     }
+
+    /// If the current word is solved or failed, but the player didn't get a new word yet
+    fn word_finished(&self) -> bool {
+        self.words.len() > self.word_index
+    }
+
+    /// Replaces the finished word with the next one
+    fn next_word(&mut self, word: Word) {
+        self.chat.retain(|m| m.from.is_none());
+        self.tries_used = 0;
+        self.word_index += 1;
+        self.word_started = Instant::now();
+        self.word = word;
+    }
+
+    /// All finished words including the word the player was working on when the time was up
+    fn round_words(&self) -> Vec<WordResult> {
+        let mut words = self.words.clone();
+        if self.word_finished() {
+            return words;
+        }
+        words.push(WordResult {
+            word: self.word.target(),
+            outcome: WordOutcome::TimeUp,
+            tries_used: self.tries_used,
+        });
+        words
+    }
+}
+
+/// The word at `index` of the round, taken from the queue if no player got this far yet
+async fn word_at(
+    index: usize,
+    words: &mut Vec<Word>,
+    upcoming: &mut WordQueue,
+) -> Result<Word, GeneratorError> {
+    if let Some(word) = words.get(index) {
+        return Ok(word.clone());
+    }
+    let word = upcoming.next().await?;
+    words.push(word.clone());
+    Ok(word)
+}
+
+fn to_score(
+    nickname: &str,
+    breakdown: &ScoreBreakdown,
+    solve_time: Duration,
+    words: Vec<WordResult>,
+) -> Score {
+    Score {
+        rank: 0,
+        nickname: nickname.to_string(),
+        score: breakdown.total(),
+        breakdown: breakdown.clone(),
+        solve_time_ms: solve_time.as_millis() as u64,
+        words,
+    }
+}
+
+/// What spectators see: the global chat without any player's word
+fn spectator_update(
+    game: &Game<CompetitiveState>,
+    countdown: Option<chrono::DateTime<Utc>>,
+    global_chat: &[ChatMessage],
+) -> ServerMessage {
+    ServerMessage::Competitive(ServerMessageInner::UpdateGame(Game {
+        state: countdown.map(|countdown| CompetitiveState {
+            chat: global_chat.to_vec(),
+            tries_used: 0,
+            word: String::new(),
+            countdown,
+        }),
+        ..game.clone()
+    }))
 }
 
 async fn round_countdown(
     code: GameCode,
+    round: u32,
+    rounds: u32,
     players: Arc<RwLock<Players>>,
     player_states: Arc<RwLock<HashMap<UserToken, PlayerState>>>,
-    results: Arc<Mutex<Option<Vec<Score>>>>,
+    results: Arc<Mutex<Option<RoundResults>>>,
 ) {
     tokio::time::sleep(
         GAME_DURATION
@@ -54,50 +172,106 @@ async fn round_countdown(
             .expect("failed to convert chrono duration to std duration"),
     )
     .await;
-    info!("[{code}] game round finished");
+    info!("[{code}] game round {round} of {rounds} finished");
 
     let players_guard = players.read().await;
-    let states_guard = player_states.read().await;
-    let mut sorted_states: Vec<(&User, &PlayerState)> = states_guard
-        .iter()
-        .filter_map(|(token, state)| players_guard.get(token).map(|(_, user)| (user, state)))
-        .collect();
-    sorted_states.sort_by_key(|(_, s)| s.score);
+    let mut states_guard = player_states.write().await;
+    let mut round_scores = vec![];
+    let mut total_scores = vec![];
+    for (token, state) in states_guard.iter_mut() {
+        let round_words = state.round_words();
+        state.total.breakdown += &state.breakdown;
+        state.total.solve_time += state.solve_time;
+        state.total.words.extend(round_words.iter().cloned());
 
-    let mut scores = Vec::with_capacity(states_guard.len());
-    let mut rank = 0;
-    let mut current_score = None;
-    for (user, state) in sorted_states.iter().rev() {
-        if current_score.map_or(true, |cs| state.score < cs) {
-            rank += 1;
+        if let Some((_, user)) = players_guard.get(token) {
+            round_scores.push(to_score(
+                &user.nickname,
+                &state.breakdown,
+                state.solve_time,
+                round_words,
+            ));
+            total_scores.push(to_score(
+                &user.nickname,
+                &state.total.breakdown,
+                state.total.solve_time,
+                state.total.words.clone(),
+            ));
         }
-        scores.push(Score {
-            rank,
-            nickname: user.nickname.clone(),
-            score: state.score,
-        });
-        current_score = Some(state.score);
     }
-    *results.lock().await = Some(scores.clone());
+    let round_results = RoundResults {
+        round,
+        rounds,
+        round_scores: scoring::rank_scores(round_scores),
+        total_scores: scoring::rank_scores(total_scores),
+    };
+    *results.lock().await = Some(round_results.clone());
 
     for (token, _) in states_guard.iter() {
         if let Some((sender, _)) = players_guard.get(token) {
             sender
-                .log_send(ServerMessage::Competitive(ServerMessageInner::Results(
-                    scores.clone(),
-                )))
+                .log_send(
+                    ServerMessage::Competitive(ServerMessageInner::Results(round_results.clone()))
+                        .into(),
+                )
                 .await;
         }
     }
+    players_guard
+        .send_to_spectators(ServerMessage::Competitive(ServerMessageInner::Results(
+            round_results,
+        )))
+        .await;
+}
+
+/// Adds a message to the chat of every player and sends them the updated game
+///
+/// No updates are sent while the results are shown.
+async fn broadcast_chat(
+    msg: ChatMessage,
+    game: &Game<CompetitiveState>,
+    countdown: Option<chrono::DateTime<Utc>>,
+    results: &Mutex<Option<RoundResults>>,
+    global_chat: &mut Vec<ChatMessage>,
+    players: &Players,
+    player_states: &mut HashMap<UserToken, PlayerState>,
+) {
+    global_chat.push(msg.clone());
+    let show_results = results.lock().await.is_some();
+    for (token, state) in player_states.iter_mut() {
+        state.chat.push(msg.clone());
+        if show_results {
+            continue;
+        }
+        if let Some((sender, _)) = players.get(token) {
+            let message = ServerMessage::Competitive(ServerMessageInner::UpdateGame(Game {
+                state: countdown.map(|_| state.to_state()),
+                ..game.clone()
+            }));
+            sender.log_send(message.into()).await;
+        }
+    }
+    if !show_results {
+        players
+            .send_to_spectators(spectator_update(game, countdown, global_chat))
+            .await;
+    }
 }
 
 pub async fn game_loop(
     mut rx: mpsc::Receiver<GameMessage>,
+    game_tx: mpsc::WeakSender<GameMessage>,
     code: GameCode,
     settings: GameSettings,
-    owner: UserToken,
+    mut owner: UserToken,
+    config: Arc<HangmanConfig>,
+    shared: Arc<SharedGame>,
 ) {
-    let players = Arc::new(RwLock::new(Players::new()));
+    let players = Arc::new(RwLock::new(Players::new(
+        game_tx,
+        Duration::from_secs(config.reconnect_grace_secs),
+        Arc::clone(&shared),
+    )));
     let mut game = Game {
         owner_hash: owner.hashed(),
         settings: settings.clone(),
@@ -107,19 +281,61 @@ pub async fn game_loop(
     let player_states: Arc<RwLock<HashMap<UserToken, PlayerState>>> =
         Arc::new(RwLock::new(HashMap::new()));
     let mut global_chat = vec![];
-    let mut words = vec![Word::new(word_generator::generate_word(&settings).await)];
+    let mut upcoming = WordQueue::spawn(settings.clone(), Arc::clone(&shared));
+    let mut words = match upcoming.next().await {
+        Ok(word) => vec![word],
+        Err(e) => {
+            error!("[{code}] failed to generate the first word: {e}");
+            return;
+        }
+    };
     let mut countdown = None;
-    let results = Arc::new(Mutex::new(None::<Vec<Score>>));
+    let mut round = 0;
+    let rounds = settings.rounds.max(1);
+    let results = Arc::new(Mutex::new(None::<RoundResults>));
 
     while let Some(msg) = rx.recv().await {
         debug!("[{code}] received {msg:?}");
+        shared.touch();
         match msg {
-            GameMessage::Join { user, sender } => {
-                info!("[{code}] {} joins the game", user.nickname);
+            GameMessage::Shutdown => {
+                // The round countdown might still hold the players
+                players.read().await.close_all(CloseCode::GameClosed).await;
+                break;
+            }
+            GameMessage::Join {
+                user,
+                sender,
+                connection,
+            } => {
+                if players
+                    .read()
+                    .await
+                    .is_full(&user.token, settings.max_players)
+                {
+                    info!("[{code}] rejecting {}, the game is full", user.nickname);
+                    sender
+                        .log_send(PlayerMessage::Close(CloseCode::GameFull))
+                        .await;
+                    continue;
+                }
+                let user = players
+                    .read()
+                    .await
+                    .with_unique_nickname(user, config.moderation.nickname_max_len);
                 let user_token = user.token;
                 let nickname = user.nickname.clone();
 
-                players.write().await.add_player(sender.clone(), user).await;
+                let resumed = players
+                    .write()
+                    .await
+                    .add_player(sender.clone(), user, connection)
+                    .await;
+                if resumed {
+                    debug!("[{code}] {nickname} reconnected");
+                } else {
+                    info!("[{code}] {nickname} joins the game");
+                }
                 game.players = players.read().await.player_names();
 
                 let mut states_guard = player_states.write().await;
@@ -127,14 +343,11 @@ pub async fn game_loop(
                     None => {
                         states_guard.insert(
                             user_token,
-                            PlayerState {
-                                tries_used: 0,
-                                chat: global_chat.clone(),
-                                countdown: countdown.unwrap_or(Utc::now()),
-                                word: words[0].clone(),
-                                word_index: 0,
-                                score: 0,
-                            },
+                            PlayerState::new(
+                                global_chat.clone(),
+                                countdown.unwrap_or(Utc::now()),
+                                words[0].clone(),
+                            ),
                         );
                     }
                     Some(_) => {
@@ -143,6 +356,16 @@ pub async fn game_loop(
                 }
 
                 match *results.lock().await {
+                    None if resumed => {
+                        if let Some(state) = states_guard.get(&user_token) {
+                            let message =
+                                ServerMessage::Competitive(ServerMessageInner::UpdateGame(Game {
+                                    state: countdown.map(|_| state.to_state()),
+                                    ..game.clone()
+                                }));
+                            sender.log_send(message.into()).await;
+                        }
+                    }
                     None => {
                         let join_msg = join_message(&nickname);
                         global_chat.push(join_msg.clone());
@@ -156,65 +379,137 @@ pub async fn game_loop(
                                         ..game.clone()
                                     }),
                                 );
-                                sender.log_send(message).await;
+                                sender.log_send(message.into()).await;
                             }
                         }
+                        players_guard
+                            .send_to_spectators(spectator_update(&game, countdown, &global_chat))
+                            .await;
                     }
                     Some(ref r) => {
                         sender
-                            .log_send(ServerMessage::Competitive(ServerMessageInner::Results(
-                                r.clone(),
-                            )))
+                            .log_send(
+                                ServerMessage::Competitive(ServerMessageInner::Results(r.clone()))
+                                    .into(),
+                            )
                             .await;
                     }
                 }
             }
-            GameMessage::Leave(token) => {
-                let Some((_, user)) = players.write().await.remove_player(&token).await else {
+            GameMessage::Leave { token, connection } => {
+                if let Some(user) = players.write().await.disconnect_player(token, connection) {
+                    debug!(
+                        "[{code}] {} disconnected, waiting for them to reconnect",
+                        user.nickname
+                    );
+                } else {
                     warn!("[{code}] there was no user in this game with this token");
-                    return;
+                }
+            }
+            GameMessage::ReconnectGraceExpired { token, session } => {
+                let mut players_guard = players.write().await;
+                let Some(user) = players_guard.expire_session(&token, session).await else {
+                    continue;
                 };
                 info!("[{code}] {} left the game", user.nickname);
 
-                game.players = players.read().await.player_names();
+                if players_guard.is_empty() {
+                    info!("[{code}] all players left the game, closing");
+                    break;
+                }
 
-                let leave_msg = leave_message(&user.nickname);
-                global_chat.push(leave_msg.clone());
-                let guard = players.read().await;
-                for (token, state) in player_states.write().await.iter_mut() {
-                    state.chat.push(leave_msg.clone());
-                    if results.lock().await.is_none() {
-                        if let Some((sender, _)) = guard.get(token) {
-                            let message =
-                                ServerMessage::Competitive(ServerMessageInner::UpdateGame(Game {
-                                    state: countdown.map(|_| state.to_state()),
-                                    ..game.clone()
-                                }));
-                            sender.log_send(message).await;
-                        }
-                    }
+                game.players = players_guard.player_names();
+                let new_owner = (token == owner)
+                    .then(|| players_guard.next_owner().cloned())
+                    .flatten();
+                if let Some(new_owner) = &new_owner {
+                    info!("[{code}] {} is the new game owner", new_owner.nickname);
+                    owner = new_owner.token;
+                    game.owner_hash = owner.hashed();
                 }
 
-                if guard.is_empty() {
-                    info!("[{code}] all players left the game, closing");
-                    break;
-                } else if token == owner {
-                    info!("[{code}] the game owner left the game, closing");
-                    break;
+                let mut states_guard = player_states.write().await;
+                broadcast_chat(
+                    leave_message(&user.nickname),
+                    &game,
+                    countdown,
+                    &results,
+                    &mut global_chat,
+                    &players_guard,
+                    &mut states_guard,
+                )
+                .await;
+                if let Some(new_owner) = new_owner {
+                    broadcast_chat(
+                        owner_message(&new_owner.nickname),
+                        &game,
+                        countdown,
+                        &results,
+                        &mut global_chat,
+                        &players_guard,
+                        &mut states_guard,
+                    )
+                    .await;
+                }
+            }
+            GameMessage::Spectate { user, sender } => {
+                info!("[{code}] {} is spectating the game", user.nickname);
+                let message = match *results.lock().await {
+                    Some(ref r) => {
+                        ServerMessage::Competitive(ServerMessageInner::Results(r.clone()))
+                    }
+                    None => spectator_update(&game, countdown, &global_chat),
+                };
+                sender.log_send(message.into()).await;
+                players.write().await.add_spectator(sender, user);
+            }
+            GameMessage::StopSpectating(token) => {
+                if let Some((_, user)) = players.write().await.remove_spectator(&token) {
+                    info!("[{code}] {} stopped spectating", user.nickname);
                 }
             }
             GameMessage::ClientMessage { message, token } => {
-                if let Some((sender, user)) = players.read().await.get(&token) {
+                let player = players.read().await.get(&token).cloned();
+                if let Some((sender, user)) = player {
                     match message {
                         ClientMessage::ChatMessage(msg) => {
                             if countdown.is_none() {
-                                return;
+                                continue;
                             }
                             let mut lock = player_states.write().await;
                             let Some(player_state) = lock.get_mut(&token) else {
                                 warn!("failed to find player state for {token}");
-                                return;
+                                continue;
                             };
+                            if player_state.word_finished() {
+                                // Generating the next word failed before, try again
+                                match word_at(
+                                    player_state.word_index + 1,
+                                    &mut words,
+                                    &mut upcoming,
+                                )
+                                .await
+                                {
+                                    Ok(word) => player_state.next_word(word),
+                                    Err(e) => {
+                                        error!("[{code}] failed to generate a word: {e}");
+                                        sender.log_send(word_error(&e).into()).await;
+                                        continue;
+                                    }
+                                }
+                                sender
+                                    .log_send(
+                                        ServerMessage::Competitive(ServerMessageInner::UpdateGame(
+                                            Game {
+                                                state: Some(player_state.to_state()),
+                                                ..game.clone()
+                                            },
+                                        ))
+                                        .into(),
+                                    )
+                                    .await;
+                                continue;
+                            }
                             let guess = player_state.word.guess(msg.clone());
                             match guess {
                                 GuessResult::Hit => {
@@ -226,16 +521,34 @@ pub async fn game_loop(
                                 }
                                 GuessResult::Solved => {
                                     info!("[{code}] {} solved the word", user.nickname);
-                                    player_state.score += 1;
+                                    let solve_time = player_state.word_started.elapsed();
+                                    player_state.solve_time += solve_time;
+                                    scoring::add_solved_word(
+                                        &config.scoring,
+                                        &mut player_state.breakdown,
+                                        player_state.word.letters(),
+                                        player_state.tries_used,
+                                        solve_time,
+                                    );
                                 }
                             }
 
                             player_state.chat.push(ChatMessage {
                                 from: Some(user.nickname.clone()),
-                                content: msg,
+                                content: shared.blocklist.mask(&msg),
                                 color: guess.clone().into(),
                             });
-                            if guess == GuessResult::Solved || player_state.tries_used == 9 {
+                            if guess == GuessResult::Solved || player_state.tries_used == MAX_TRIES
+                            {
+                                let result = WordResult {
+                                    word: player_state.word.target(),
+                                    outcome: if guess == GuessResult::Solved {
+                                        WordOutcome::Solved
+                                    } else {
+                                        WordOutcome::Failed
+                                    },
+                                    tries_used: player_state.tries_used,
+                                };
                                 let chat_msg = if guess == GuessResult::Solved {
                                     ChatMessage {
                                         content: format!(
@@ -256,27 +569,41 @@ pub async fn game_loop(
                                     }
                                 };
                                 player_state.chat.push(chat_msg.clone());
+                                player_state.words.push(result.clone());
+                                sender
+                                    .log_send(
+                                        ServerMessage::Competitive(
+                                            ServerMessageInner::WordFinished(result),
+                                        )
+                                        .into(),
+                                    )
+                                    .await;
 
-                                // New word
-                                player_state.chat.retain(|m| m.from.is_none());
-                                player_state.tries_used = 0;
-                                player_state.word_index += 1;
-                                if let Some(new_word) = words.get(player_state.word_index) {
-                                    player_state.word = new_word.clone();
-                                } else {
-                                    let new_word =
-                                        Word::new(word_generator::generate_word(&settings).await);
-                                    player_state.word = new_word.clone();
-                                    words.push(new_word);
+                                // New word, the finished one is kept if none can be generated
+                                match word_at(
+                                    player_state.word_index + 1,
+                                    &mut words,
+                                    &mut upcoming,
+                                )
+                                .await
+                                {
+                                    Ok(word) => player_state.next_word(word),
+                                    Err(e) => {
+                                        error!("[{code}] failed to generate a word: {e}");
+                                        sender.log_send(word_error(&e).into()).await;
+                                    }
                                 }
                             }
                             sender
-                                .log_send(ServerMessage::Competitive(
-                                    ServerMessageInner::UpdateGame(Game {
-                                        state: Some(player_state.to_state()),
-                                        ..game.clone()
-                                    }),
-                                ))
+                                .log_send(
+                                    ServerMessage::Competitive(ServerMessageInner::UpdateGame(
+                                        Game {
+                                            state: Some(player_state.to_state()),
+                                            ..game.clone()
+                                        },
+                                    ))
+                                    .into(),
+                                )
                                 .await;
                         }
                         ClientMessage::NextRound => {
@@ -292,22 +619,36 @@ pub async fn game_loop(
                                     global_chat.push(msg.clone());
                                     let ctdwn = Utc::now() + *GAME_DURATION;
                                     countdown = Some(ctdwn);
+                                    round = 1;
                                     for (token, state) in player_states.write().await.iter_mut() {
                                         state.chat.push(msg.clone());
                                         state.countdown = ctdwn;
+                                        state.word_started = Instant::now();
                                         if let Some((sender, _)) = guard.get(token) {
                                             sender
-                                                .log_send(ServerMessage::Competitive(
-                                                    ServerMessageInner::UpdateGame(Game {
-                                                        state: Some(state.to_state()),
-                                                        ..game.clone()
-                                                    }),
-                                                ))
+                                                .log_send(
+                                                    ServerMessage::Competitive(
+                                                        ServerMessageInner::UpdateGame(Game {
+                                                            state: Some(state.to_state()),
+                                                            ..game.clone()
+                                                        }),
+                                                    )
+                                                    .into(),
+                                                )
                                                 .await;
                                         }
                                     }
+                                    guard
+                                        .send_to_spectators(spectator_update(
+                                            &game,
+                                            countdown,
+                                            &global_chat,
+                                        ))
+                                        .await;
                                     tokio::spawn(round_countdown(
                                         code,
+                                        round,
+                                        rounds,
                                         Arc::clone(&players),
                                         Arc::clone(&player_states),
                                         Arc::clone(&results),
@@ -319,31 +660,61 @@ pub async fn game_loop(
                                     );
                                 }
                             } else {
-                                // New round
-                                info!("[{code}] {} started a new round", user.nickname);
-                                let new_round_msg = ChatMessage {
-                                    content: format!("{} started a new round", user.nickname),
-                                    ..Default::default()
+                                let mut results_guard = results.lock().await;
+                                let Some(previous) = results_guard.as_ref() else {
+                                    warn!(
+                                        "can't start a new round while the round is still running"
+                                    );
+                                    continue;
+                                };
+                                let first_word = match upcoming.next().await {
+                                    Ok(word) => word,
+                                    Err(e) => {
+                                        // The results stay up, so the round can be started again
+                                        error!("[{code}] failed to generate a word: {e}");
+                                        drop(results_guard);
+                                        players.read().await.send_to_all(word_error(&e)).await;
+                                        continue;
+                                    }
+                                };
+                                let new_match = previous.match_finished();
+                                results_guard.take();
+                                drop(results_guard);
+
+                                // New round, or a new match if the last one is finished
+                                let content = if new_match {
+                                    round = 1;
+                                    format!("{} started a new match", user.nickname)
+                                } else {
+                                    round += 1;
+                                    format!("{} started round {round} of {rounds}", user.nickname)
                                 };
-                                global_chat = vec![new_round_msg];
-                                words =
-                                    vec![Word::new(word_generator::generate_word(&settings).await)];
+                                info!("[{code}] {content}");
+                                global_chat = vec![ChatMessage {
+                                    content,
+                                    ..Default::default()
+                                }];
+                                words = vec![first_word];
                                 let ctdwn = Utc::now() + *GAME_DURATION;
                                 countdown = Some(ctdwn);
-                                *results.lock().await = None;
                                 for p in player_states.write().await.values_mut() {
+                                    let total = if new_match {
+                                        MatchTotal::default()
+                                    } else {
+                                        std::mem::take(&mut p.total)
+                                    };
                                     *p = PlayerState {
-                                        tries_used: 0,
-                                        chat: global_chat.clone(),
-                                        countdown: ctdwn,
-                                        word: words[0].clone(),
-                                        word_index: 0,
-                                        score: 0,
+                                        total,
+                                        ..PlayerState::new(
+                                            global_chat.clone(),
+                                            ctdwn,
+                                            words[0].clone(),
+                                        )
                                     };
                                 }
                                 let guard = players.read().await;
                                 guard
-                                    .send_to_all(ServerMessage::Competitive(
+                                    .send_to_players(ServerMessage::Competitive(
                                         ServerMessageInner::UpdateGame(Game {
                                             state: Some(CompetitiveState {
                                                 chat: global_chat.clone(),
@@ -355,14 +726,92 @@ pub async fn game_loop(
                                         }),
                                     ))
                                     .await;
+                                guard
+                                    .send_to_spectators(spectator_update(
+                                        &game,
+                                        countdown,
+                                        &global_chat,
+                                    ))
+                                    .await;
                                 tokio::spawn(round_countdown(
                                     code,
+                                    round,
+                                    rounds,
                                     Arc::clone(&players),
                                     Arc::clone(&player_states),
                                     Arc::clone(&results),
                                 ));
                             }
                         }
+                        ClientMessage::TransferOwnership(nickname) => {
+                            if user.token != owner {
+                                warn!(
+                                    "{} tried to transfer the ownership, but is not owner",
+                                    user.nickname
+                                );
+                                continue;
+                            }
+                            let players_guard = players.read().await;
+                            let Some(new_owner) = players_guard.find_by_nickname(&nickname) else {
+                                warn!("[{code}] there is no player called {nickname}");
+                                continue;
+                            };
+                            info!("[{code}] {} made {nickname} the game owner", user.nickname);
+                            owner = new_owner.token;
+                            game.owner_hash = owner.hashed();
+                            broadcast_chat(
+                                owner_message(&nickname),
+                                &game,
+                                countdown,
+                                &results,
+                                &mut global_chat,
+                                &players_guard,
+                                &mut *player_states.write().await,
+                            )
+                            .await;
+                        }
+                        ClientMessage::Kick(ref nickname) | ClientMessage::Ban(ref nickname) => {
+                            let ban = matches!(message, ClientMessage::Ban(_));
+                            if user.token != owner {
+                                warn!("{} tried to kick a player, but is not owner", user.nickname);
+                                continue;
+                            }
+                            let mut players_guard = players.write().await;
+                            let Some(kicked_token) = players_guard
+                                .find_by_nickname(nickname)
+                                .map(|u| u.token)
+                                .filter(|t| *t != owner)
+                            else {
+                                warn!("[{code}] there is no other player called {nickname}");
+                                continue;
+                            };
+
+                            let close_code = if ban {
+                                shared.ban(kicked_token);
+                                CloseCode::Banned
+                            } else {
+                                CloseCode::Kicked
+                            };
+                            if let Some(kicked) =
+                                players_guard.kick_player(&kicked_token, close_code).await
+                            {
+                                info!(
+                                    "[{code}] {} removed {} ({close_code:?})",
+                                    user.nickname, kicked.nickname
+                                );
+                                game.players = players_guard.player_names();
+                                broadcast_chat(
+                                    kick_message(&kicked.nickname, ban),
+                                    &game,
+                                    countdown,
+                                    &results,
+                                    &mut global_chat,
+                                    &players_guard,
+                                    &mut *player_states.write().await,
+                                )
+                                .await;
+                            }
+                        }
                     }
                 } else {
                     warn!("[{code}] there was no user in this game with this token");
@@ -0,0 +1,109 @@
+use crate::config::ScoringConfig;
+use hangman_data::{Score, ScoreBreakdown, MAX_TRIES};
+use std::time::Duration;
+
+/// Adds the points for a solved word to the given breakdown
+pub fn add_solved_word(
+    config: &ScoringConfig,
+    breakdown: &mut ScoreBreakdown,
+    letters: usize,
+    tries_used: u32,
+    solve_time: Duration,
+) {
+    breakdown.words_solved += 1;
+    breakdown.base += config.base_points;
+    breakdown.length += config.points_per_letter * letters as u32;
+    breakdown.tries += config.points_per_try_left * MAX_TRIES.saturating_sub(tries_used);
+
+    let bonus_secs = config.speed_bonus_secs * 1000;
+    if bonus_secs > 0 {
+        let remaining = bonus_secs.saturating_sub(solve_time.as_millis() as u64);
+        breakdown.speed += (config.speed_bonus as u64 * remaining / bonus_secs) as u32;
+    }
+}
+
+/// Sorts the scores and assigns ranks
+///
+/// Higher scores rank first, ties are broken by the lower total solve time.
+pub fn rank_scores(mut scores: Vec<Score>) -> Vec<Score> {
+    scores.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.solve_time_ms.cmp(&b.solve_time_ms))
+    });
+
+    let mut rank = 0;
+    let mut previous = None;
+    for score in &mut scores {
+        let key = (score.score, score.solve_time_ms);
+        if previous != Some(key) {
+            rank += 1;
+        }
+        score.rank = rank;
+        previous = Some(key);
+    }
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: ScoringConfig = ScoringConfig {
+        base_points: 10,
+        points_per_letter: 1,
+        points_per_try_left: 2,
+        speed_bonus: 10,
+        speed_bonus_secs: 30,
+    };
+
+    fn score(nickname: &str, score: u32, solve_time_ms: u64) -> Score {
+        Score {
+            rank: 0,
+            nickname: nickname.to_string(),
+            score,
+            breakdown: ScoreBreakdown::default(),
+            solve_time_ms,
+            words: vec![],
+        }
+    }
+
+    #[test]
+    fn solved_word() {
+        let mut breakdown = ScoreBreakdown::default();
+        add_solved_word(&CONFIG, &mut breakdown, 6, 3, Duration::from_secs(15));
+        assert_eq!(
+            breakdown,
+            ScoreBreakdown {
+                words_solved: 1,
+                base: 10,
+                length: 6,
+                tries: 12,
+                speed: 5,
+            }
+        );
+
+        add_solved_word(&CONFIG, &mut breakdown, 4, 9, Duration::from_secs(60));
+        assert_eq!(breakdown.words_solved, 2);
+        assert_eq!(breakdown.speed, 5);
+        assert_eq!(breakdown.total(), 20 + 10 + 12 + 5);
+    }
+
+    #[test]
+    fn ranking() {
+        let ranked = rank_scores(vec![
+            score("slow", 30, 9000),
+            score("best", 50, 20000),
+            score("fast", 30, 4000),
+            score("tie", 30, 4000),
+        ]);
+        let ranks: Vec<(&str, u32)> = ranked
+            .iter()
+            .map(|s| (s.nickname.as_str(), s.rank))
+            .collect();
+        assert_eq!(
+            ranks,
+            vec![("best", 1), ("fast", 2), ("tie", 2), ("slow", 3)]
+        );
+    }
+}
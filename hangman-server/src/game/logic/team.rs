@@ -1,27 +1,45 @@
 use crate::{
-    game::logic::{
-        join_message, leave_message,
-        word::{GuessResult, Word},
-        GameMessage, Players,
+    config::HangmanConfig,
+    game::{
+        logic::{
+            join_message, kick_message, leave_message, owner_message, word::GuessResult,
+            word_error, word_queue::WordQueue, GameMessage, PlayerMessage, Players,
+        },
+        SharedGame,
     },
-    word_generator,
+    sender_utils::LogSend,
 };
 use hangman_data::{
-    ChatColor, ChatMessage, ClientMessage, Game, GameCode, GameSettings, ServerMessage,
-    ServerMessageInner, TeamState, UserToken,
+    ChatColor, ChatMessage, ClientMessage, CloseCode, Game, GameCode, GameSettings, ServerMessage,
+    ServerMessageInner, TeamState, UserToken, MAX_TRIES,
 };
+use std::{sync::Arc, time::Duration};
 use tokio::sync::mpsc;
-use tracing::{debug, info, log::warn};
+use tracing::{debug, error, info, log::warn};
 
 pub async fn game_loop(
     mut rx: mpsc::Receiver<GameMessage>,
+    game_tx: mpsc::WeakSender<GameMessage>,
     code: GameCode,
     settings: GameSettings,
-    owner: UserToken,
+    mut owner: UserToken,
+    config: Arc<HangmanConfig>,
+    shared: Arc<SharedGame>,
 ) {
-    let mut players = Players::new();
+    let mut players = Players::new(
+        game_tx,
+        Duration::from_secs(config.reconnect_grace_secs),
+        Arc::clone(&shared),
+    );
     let mut chat = vec![];
-    let mut word = Word::new(word_generator::generate_word(&settings).await);
+    let mut upcoming = WordQueue::spawn(settings.clone(), Arc::clone(&shared));
+    let mut word = match upcoming.next().await {
+        Ok(word) => word,
+        Err(e) => {
+            error!("[{code}] failed to generate the first word: {e}");
+            return;
+        }
+    };
     let mut game = Game::<TeamState> {
         owner_hash: owner.hashed(),
         settings: settings.clone(),
@@ -31,11 +49,37 @@ pub async fn game_loop(
 
     'game_loop: while let Some(msg) = rx.recv().await {
         debug!("[{code}] received {msg:?}");
+        shared.touch();
         match msg {
-            GameMessage::Join { user, sender } => {
-                info!("[{code}] {} joins the game", user.nickname);
+            GameMessage::Shutdown => {
+                players.close_all(CloseCode::GameClosed).await;
+                break 'game_loop;
+            }
+            GameMessage::Join {
+                user,
+                sender,
+                connection,
+            } => {
+                if players.is_full(&user.token, settings.max_players) {
+                    info!("[{code}] rejecting {}, the game is full", user.nickname);
+                    sender
+                        .log_send(PlayerMessage::Close(CloseCode::GameFull))
+                        .await;
+                    continue;
+                }
+                let user = players.with_unique_nickname(user, config.moderation.nickname_max_len);
                 let nickname = user.nickname.clone();
-                players.add_player(sender, user).await;
+                if players.add_player(sender.clone(), user, connection).await {
+                    debug!("[{code}] {nickname} reconnected");
+                    sender
+                        .log_send(
+                            ServerMessage::Team(ServerMessageInner::UpdateGame(game.clone()))
+                                .into(),
+                        )
+                        .await;
+                    continue;
+                }
+                info!("[{code}] {nickname} joins the game");
                 chat.push(join_message(&nickname));
                 // If game is started
                 game.players = players.player_names();
@@ -48,14 +92,36 @@ pub async fn game_loop(
                     )))
                     .await;
             }
-            GameMessage::Leave(token) => {
-                let Some((_, user)) = players.remove_player(&token).await else {
+            GameMessage::Leave { token, connection } => {
+                if let Some(user) = players.disconnect_player(token, connection) {
+                    debug!(
+                        "[{code}] {} disconnected, waiting for them to reconnect",
+                        user.nickname
+                    );
+                } else {
                     warn!("[{code}] there was no user in this game with this token");
-                    return;
+                }
+            }
+            GameMessage::ReconnectGraceExpired { token, session } => {
+                let Some(user) = players.expire_session(&token, session).await else {
+                    continue;
                 };
                 info!("[{code}] {} left the game", user.nickname);
 
+                if players.is_empty() {
+                    info!("[{code}] all players left the game, closing");
+                    break 'game_loop;
+                }
+
                 chat.push(leave_message(&user.nickname));
+                if token == owner {
+                    if let Some(new_owner) = players.next_owner() {
+                        info!("[{code}] {} is the new game owner", new_owner.nickname);
+                        owner = new_owner.token;
+                        game.owner_hash = owner.hashed();
+                        chat.push(owner_message(&new_owner.nickname));
+                    }
+                }
 
                 // If game is started
                 game.players = players.player_names();
@@ -67,17 +133,23 @@ pub async fn game_loop(
                         game.clone(),
                     )))
                     .await;
-
-                if players.is_empty() {
-                    info!("[{code}] all players left the game, closing");
-                    break 'game_loop;
-                } else if token == owner {
-                    info!("[{code}] the game owner left the game, closing");
-                    break 'game_loop;
+            }
+            GameMessage::Spectate { user, sender } => {
+                info!("[{code}] {} is spectating the game", user.nickname);
+                sender
+                    .log_send(
+                        ServerMessage::Team(ServerMessageInner::UpdateGame(game.clone())).into(),
+                    )
+                    .await;
+                players.add_spectator(sender, user);
+            }
+            GameMessage::StopSpectating(token) => {
+                if let Some((_, user)) = players.remove_spectator(&token) {
+                    info!("[{code}] {} stopped spectating", user.nickname);
                 }
             }
             GameMessage::ClientMessage { message, token } => {
-                if let Some((_, user)) = players.get(&token) {
+                if let Some(user) = players.get(&token).map(|(_, u)| u.clone()) {
                     match message {
                         ClientMessage::ChatMessage(message) => {
                             // If game is started
@@ -99,11 +171,12 @@ pub async fn game_loop(
 
                                 chat.push(ChatMessage {
                                     from: Some(user.nickname.clone()),
-                                    content: message,
+                                    content: shared.blocklist.mask(&message),
                                     color: guess.clone().into(),
                                 });
 
-                                state.round_finished = guess == GuessResult::Solved || state.tries_used == 9;
+                                state.round_finished =
+                                    guess == GuessResult::Solved || state.tries_used == MAX_TRIES;
                                 if state.round_finished {
                                     if guess == GuessResult::Solved {
                                         chat.push(ChatMessage {
@@ -157,9 +230,17 @@ pub async fn game_loop(
                                 }
                             }
                             Some(state) if state.round_finished => {
+                                match upcoming.next().await {
+                                    Ok(w) => word = w,
+                                    Err(e) => {
+                                        // The finished round stays up, so it can be started again
+                                        error!("[{code}] failed to generate a word: {e}");
+                                        players.send_to_all(word_error(&e)).await;
+                                        continue;
+                                    }
+                                }
                                 chat.retain(|m| m.from.is_none());
                                 state.tries_used = 0;
-                                word = Word::new(word_generator::generate_word(&settings).await);
                                 chat.push(ChatMessage {
                                     content: format!("{} started a new round", user.nickname),
                                     ..Default::default()
@@ -178,6 +259,71 @@ pub async fn game_loop(
                                 warn!("can't start a new round when game is still `Started`");
                             }
                         },
+                        ClientMessage::TransferOwnership(nickname) => {
+                            if user.token != owner {
+                                warn!(
+                                    "{} tried to transfer the ownership, but is not owner",
+                                    user.nickname
+                                );
+                                continue;
+                            }
+                            let Some(new_owner) = players.find_by_nickname(&nickname) else {
+                                warn!("[{code}] there is no player called {nickname}");
+                                continue;
+                            };
+                            info!("[{code}] {} made {nickname} the game owner", user.nickname);
+                            owner = new_owner.token;
+                            game.owner_hash = owner.hashed();
+                            chat.push(owner_message(&nickname));
+                            if let Some(state) = &mut game.state {
+                                state.chat = chat.clone();
+                            }
+                            players
+                                .send_to_all(ServerMessage::Team(ServerMessageInner::UpdateGame(
+                                    game.clone(),
+                                )))
+                                .await;
+                        }
+                        ClientMessage::Kick(ref nickname) | ClientMessage::Ban(ref nickname) => {
+                            let ban = matches!(message, ClientMessage::Ban(_));
+                            if user.token != owner {
+                                warn!("{} tried to kick a player, but is not owner", user.nickname);
+                                continue;
+                            }
+                            let Some(kicked_token) = players
+                                .find_by_nickname(nickname)
+                                .map(|u| u.token)
+                                .filter(|t| *t != owner)
+                            else {
+                                warn!("[{code}] there is no other player called {nickname}");
+                                continue;
+                            };
+
+                            let close_code = if ban {
+                                shared.ban(kicked_token);
+                                CloseCode::Banned
+                            } else {
+                                CloseCode::Kicked
+                            };
+                            if let Some(kicked) =
+                                players.kick_player(&kicked_token, close_code).await
+                            {
+                                info!(
+                                    "[{code}] {} removed {} ({close_code:?})",
+                                    user.nickname, kicked.nickname
+                                );
+                                chat.push(kick_message(&kicked.nickname, ban));
+                                game.players = players.player_names();
+                                if let Some(state) = &mut game.state {
+                                    state.chat = chat.clone();
+                                }
+                                players
+                                    .send_to_all(ServerMessage::Team(
+                                        ServerMessageInner::UpdateGame(game.clone()),
+                                    ))
+                                    .await;
+                            }
+                        }
                     }
                 } else {
                     warn!("[{code}] there was no user in this game with this token");
@@ -1,4 +1,4 @@
-use hangman_data::ChatColor;
+use hangman_data::{wordlist::CaseRules, ChatColor};
 use std::fmt::{Display, Formatter};
 use unicode_segmentation::UnicodeSegmentation;
 
@@ -6,6 +6,7 @@ use unicode_segmentation::UnicodeSegmentation;
 pub struct Word {
     target: Vec<String>,
     current: Vec<Character>,
+    case: CaseRules,
 }
 
 #[derive(Clone, PartialEq)]
@@ -41,11 +42,12 @@ impl Display for Character {
 }
 
 impl Word {
-    pub fn new(target: String) -> Self {
+    pub fn new(target: String, case: CaseRules) -> Self {
         let target: Vec<String> = target.graphemes(true).map(|s| s.to_string()).collect();
         Self {
             current: vec![Character::Unknown; target.len()],
             target,
+            case,
         }
     }
 
@@ -53,6 +55,11 @@ impl Word {
         self.target.join("")
     }
 
+    /// Number of graphemes in the target word
+    pub fn letters(&self) -> usize {
+        self.target.len()
+    }
+
     pub fn word(&self) -> String {
         self.current
             .iter()
@@ -61,11 +68,11 @@ impl Word {
 
     pub fn guess(&mut self, s: String) -> GuessResult {
         // TODO: Feels a bit messy
-        let graphemes: Vec<String> = s.graphemes(true).map(|s| s.to_lowercase()).collect();
+        let graphemes: Vec<String> = s.graphemes(true).map(|s| self.case.fold(s)).collect();
         if self
             .target
             .iter()
-            .map(|s| s.to_lowercase())
+            .map(|s| self.case.fold(s))
             .collect::<Vec<String>>()
             == graphemes
         {
@@ -76,13 +83,13 @@ impl Word {
                 .collect();
             GuessResult::Solved
         } else if graphemes.len() == 1 {
-            if let Some(g) = graphemes.get(0) {
+            if let Some(g) = graphemes.first() {
                 let mut found = false;
                 for (i, _) in self
                     .target
                     .iter()
                     .enumerate()
-                    .filter(|t| t.1.to_lowercase() == *g)
+                    .filter(|t| self.case.fold(t.1) == *g)
                 {
                     self.current[i] = Character::Guessed(self.target[i].clone());
                     found = true;
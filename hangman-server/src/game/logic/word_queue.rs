@@ -0,0 +1,50 @@
+//! Words a game will use next, generated in the background
+
+use crate::{
+    game::{logic::word::Word, SharedGame},
+    word_generator::{self, GeneratorError, UsedWords},
+};
+use hangman_data::GameSettings;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Number of words that are generated in advance
+const PREFETCHED_WORDS: usize = 3;
+
+/// Upcoming words of a game
+///
+/// Words are taken from the wordlists at the time they are generated,
+/// so up to [`PREFETCHED_WORDS`] words of old wordlists are used after a reload.
+#[derive(Debug)]
+pub struct WordQueue {
+    rx: mpsc::Receiver<Result<Word, GeneratorError>>,
+}
+
+impl WordQueue {
+    /// Starts generating words, generation stops when the queue is dropped
+    pub fn spawn(settings: GameSettings, shared: Arc<SharedGame>) -> Self {
+        let (tx, rx) = mpsc::channel(PREFETCHED_WORDS);
+        let case = word_generator::case_rules(&settings.language);
+        tokio::spawn(async move {
+            let mut used_words = UsedWords::new(case);
+            loop {
+                let word = word_generator::generate_word(
+                    &settings,
+                    shared.custom_words.as_ref(),
+                    &mut used_words,
+                );
+                if tx.send(word.map(|w| Word::new(w, case))).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Self { rx }
+    }
+
+    /// The next word, only waits if the words are used up faster than they are generated
+    ///
+    /// Fails with [`GeneratorError::Stopped`] if the generating task ended, e.g. because it panicked.
+    pub async fn next(&mut self) -> Result<Word, GeneratorError> {
+        self.rx.recv().await.unwrap_or(Err(GeneratorError::Stopped))
+    }
+}
@@ -0,0 +1,116 @@
+//! Languages the server has wordlists for
+//!
+//! The languages are listed in `languages.toml` in the wordlists directory.
+
+use config::Config;
+use hangman_data::{wordlist::CaseRules, GameLanguage, LanguageInfo};
+use serde::Deserialize;
+use std::{collections::HashSet, path::PathBuf};
+use thiserror::Error;
+use unicode_segmentation::UnicodeSegmentation;
+
+const MANIFEST_FILE: &str = "languages.toml";
+
+#[derive(Debug, Error)]
+pub enum LanguagesError {
+    #[error("failed to read language manifest: {0}")]
+    Config(#[from] config::ConfigError),
+    #[error("language {0} is listed twice")]
+    Duplicate(GameLanguage),
+    #[error("no languages are listed")]
+    Empty,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Language {
+    pub code: GameLanguage,
+    /// Name of the language in the language itself
+    pub name: String,
+    /// Wordlist file in the wordlists directory
+    pub file: String,
+    /// Letters words can consist of, words with other letters are skipped
+    ///
+    /// All words are allowed if this is empty.
+    #[serde(default)]
+    pub alphabet: String,
+    #[serde(default)]
+    pub case: CaseRules,
+    /// Words of these categories are skipped, only wordlists in the current format have categories
+    #[serde(default)]
+    pub exclude_categories: Vec<String>,
+    /// Folded letters of the alphabet
+    #[serde(skip)]
+    letters: HashSet<String>,
+}
+
+impl Language {
+    pub fn wordlist_path(&self, wordlists_dir: &str) -> PathBuf {
+        let mut path = PathBuf::new();
+        path.push(wordlists_dir);
+        path.push(&self.file);
+        path
+    }
+
+    /// If all letters of the word are part of the alphabet
+    pub fn is_valid_word(&self, word: &str) -> bool {
+        self.letters.is_empty()
+            || word
+                .graphemes(true)
+                .all(|l| self.letters.contains(&self.case.fold(l)))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    language: Vec<Language>,
+}
+
+/// All languages from the manifest, in the order they are listed
+#[derive(Debug)]
+pub struct Languages(Vec<Language>);
+
+impl Languages {
+    pub fn load(wordlists_dir: &str) -> Result<Self, LanguagesError> {
+        let mut path = PathBuf::new();
+        path.push(wordlists_dir);
+        path.push(MANIFEST_FILE);
+        let mut manifest: Manifest = Config::builder()
+            .add_source(config::File::from(path.as_path()))
+            .build()?
+            .try_deserialize()?;
+
+        if manifest.language.is_empty() {
+            return Err(LanguagesError::Empty);
+        }
+        let mut codes = HashSet::new();
+        for lang in &mut manifest.language {
+            if !codes.insert(lang.code.clone()) {
+                return Err(LanguagesError::Duplicate(lang.code.clone()));
+            }
+            lang.letters = lang
+                .alphabet
+                .graphemes(true)
+                .map(|l| lang.case.fold(l))
+                .collect();
+        }
+        Ok(Self(manifest.language))
+    }
+
+    pub fn get(&self, code: &GameLanguage) -> Option<&Language> {
+        self.0.iter().find(|l| l.code == *code)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Language> {
+        self.0.iter()
+    }
+
+    pub fn infos(&self) -> Vec<LanguageInfo> {
+        self.0
+            .iter()
+            .map(|l| LanguageInfo {
+                code: l.code.clone(),
+                name: l.name.clone(),
+            })
+            .collect()
+    }
+}
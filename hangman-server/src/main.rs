@@ -1,13 +1,13 @@
 use crate::{
     game::GameManager,
-    word_generator::{WordGenerator, GENERATOR},
+    word_generator::{reload, WordGenerator, GENERATOR},
 };
+use arc_swap::ArcSwap;
 use axum::{
     routing::{get, post},
     Router,
 };
-use hangman_data::GameLanguage;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use tower_http::{
     services::{ServeDir, ServeFile},
     trace::TraceLayer,
@@ -18,6 +18,10 @@ use tracing_subscriber::{filter::LevelFilter, EnvFilter};
 mod api;
 mod config;
 mod game;
+mod languages;
+mod moderation;
+mod password;
+mod rate_limit;
 mod sender_utils;
 mod word_generator;
 
@@ -32,25 +36,32 @@ async fn main() {
         .init();
 
     debug!("loading config");
-    let config = config::load_config();
+    let config = Arc::new(config::load_config());
 
+    let generator = WordGenerator::load(&config.wordlists_dir, &config.moderation.blocklists_dir)
+        .await
+        .expect("failed to load wordlists");
     GENERATOR
-        .set(
-            WordGenerator::preprocess(&config, &GameLanguage::all())
-                .await
-                .expect("failed to preprocess wordlists"),
-        )
+        .set(ArcSwap::from_pointee(generator))
         .expect("failed to set global state");
+    reload::spawn_reloader(Arc::clone(&config));
+
+    let game_manager = GameManager::new(Arc::clone(&config));
+    game_manager.spawn_reaper();
 
     info!("starting hangman server on port {}", config.port);
     let app = Router::new()
         .route("/api/game", post(api::create_game))
+        .route("/api/games", get(api::public_games))
+        .route("/api/languages", get(api::languages))
+        .route("/api/quickmatch", post(api::quickmatch))
+        .route("/api/admin/reload", post(api::reload_wordlists))
         .route("/api/game/:code/ws", get(api::game_ws))
         .fallback_service(
             ServeDir::new(&config.public_dir)
                 .not_found_service(ServeFile::new(format!("{}/index.html", config.public_dir))),
         )
-        .with_state(GameManager::new())
+        .with_state(game_manager)
         .layer(TraceLayer::new_for_http());
 
     let addr = SocketAddr::new(config.address, config.port);
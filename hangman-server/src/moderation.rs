@@ -0,0 +1,224 @@
+//! Validation of nicknames and filtering of offensive words
+//!
+//! Words are compared by their confusable skeleton, so `Ηangman` with a greek `Η` is the same as
+//! `hangman`.
+
+use crate::{config::ModerationConfig, languages::Languages};
+use hangman_data::GameLanguage;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
+use thiserror::Error;
+use tokio::{fs, io};
+use tracing::{debug, info};
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::skeleton;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum NicknameError {
+    #[error("nickname must be between {0} and {1} characters long")]
+    Length(usize, usize),
+    #[error("nickname contains a blocked word")]
+    Blocked,
+}
+
+/// Normalizes the nickname and checks if it is allowed
+pub fn validate_nickname(
+    nickname: &str,
+    config: &ModerationConfig,
+    blocklist: &Blocklist,
+) -> Result<String, NicknameError> {
+    let nickname = normalize_nickname(nickname);
+    let len = nickname.graphemes(true).count();
+    if len < config.nickname_min_len || len > config.nickname_max_len {
+        return Err(NicknameError::Length(
+            config.nickname_min_len,
+            config.nickname_max_len,
+        ));
+    }
+    if blocklist.contains_blocked(&nickname) {
+        return Err(NicknameError::Blocked);
+    }
+    Ok(nickname)
+}
+
+/// Applies NFKC normalization, removes invisible characters and collapses whitespace
+fn normalize_nickname(nickname: &str) -> String {
+    nickname
+        .nfkc()
+        .filter(|c| !c.is_control() && !is_invisible(*c))
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{034F}'
+            | '\u{115F}'
+            | '\u{1160}'
+            | '\u{180E}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{206F}'
+            | '\u{3164}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FEFF}'
+            | '\u{FFA0}'
+    )
+}
+
+/// Form of a string that is the same for strings that look alike
+pub fn comparable(s: &str) -> String {
+    skeleton(&s.to_lowercase())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Offensive words of a language
+#[derive(Debug, Default)]
+pub struct Blocklist {
+    words: HashSet<String>,
+}
+
+impl Blocklist {
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            words: words
+                .into_iter()
+                .map(|w| comparable(w.as_ref().trim()))
+                .filter(|w| !w.is_empty())
+                .collect(),
+        }
+    }
+
+    fn is_blocked(&self, word: &str) -> bool {
+        self.words.contains(&comparable(word))
+    }
+
+    /// If any word of the text is blocked, also when its letters are separated by spaces or symbols
+    pub fn contains_blocked(&self, text: &str) -> bool {
+        let joined: String = text.chars().filter(|c| c.is_alphanumeric()).collect();
+        text.unicode_words().any(|w| self.is_blocked(w)) || self.is_blocked(&joined)
+    }
+
+    /// Replaces every blocked word with asterisks
+    pub fn mask(&self, text: &str) -> String {
+        if self.words.is_empty() {
+            return text.to_string();
+        }
+        text.split_word_bounds()
+            .map(|w| {
+                if self.is_blocked(w) {
+                    "*".repeat(w.graphemes(true).count())
+                } else {
+                    w.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Blocklists of all languages
+#[derive(Debug, Default)]
+pub struct Blocklists(HashMap<GameLanguage, Arc<Blocklist>>);
+
+impl Blocklists {
+    /// Reads one file per language with one word per line, missing files are treated as empty
+    pub async fn load(blocklists_dir: &str, languages: &Languages) -> io::Result<Self> {
+        let mut lists = HashMap::new();
+        for lang in languages.iter().map(|l| &l.code) {
+            let blocklist =
+                match fs::read_to_string(blocklist_path_for_language(blocklists_dir, lang)).await {
+                    Ok(file) => Blocklist::new(file.lines()),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                        debug!("no blocklist for {lang}");
+                        Blocklist::default()
+                    }
+                    Err(e) => return Err(e),
+                };
+            info!("loaded {} blocked words for {lang}", blocklist.words.len());
+            lists.insert(lang.clone(), Arc::new(blocklist));
+        }
+        Ok(Self(lists))
+    }
+
+    pub fn get(&self, lang: &GameLanguage) -> Arc<Blocklist> {
+        self.0.get(lang).cloned().unwrap_or_default()
+    }
+}
+
+fn blocklist_path_for_language(blocklists_dir: &str, lang: &GameLanguage) -> PathBuf {
+    let mut path = PathBuf::new();
+    path.push(blocklists_dir);
+    path.push(format!("{lang}.txt"));
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: ModerationConfig = ModerationConfig {
+        nickname_min_len: 2,
+        nickname_max_len: 8,
+        blocklists_dir: String::new(),
+    };
+
+    #[test]
+    fn nickname_normalization() {
+        let blocklist = Blocklist::default();
+        assert_eq!(
+            validate_nickname("  ｈａｎｇ \t\u{200B}man ", &CONFIG, &blocklist),
+            Ok("hang man".to_string())
+        );
+        assert_eq!(
+            validate_nickname(" a\u{200B} ", &CONFIG, &blocklist),
+            Err(NicknameError::Length(2, 8))
+        );
+        assert_eq!(
+            validate_nickname("hangman42", &CONFIG, &blocklist),
+            Err(NicknameError::Length(2, 8))
+        );
+    }
+
+    #[test]
+    fn blocked_nicknames() {
+        let blocklist = Blocklist::new(["Gallows", "rope"]);
+        assert_eq!(
+            validate_nickname("gallows", &CONFIG, &blocklist),
+            Err(NicknameError::Blocked)
+        );
+        // Cyrillic а and о
+        assert_eq!(
+            validate_nickname("gаllоws", &CONFIG, &blocklist),
+            Err(NicknameError::Blocked)
+        );
+        assert_eq!(
+            validate_nickname("r.o.p.e", &CONFIG, &blocklist),
+            Err(NicknameError::Blocked)
+        );
+        assert!(blocklist.contains_blocked("g a l l o w s"));
+        assert!(!blocklist.contains_blocked("gallow"));
+    }
+
+    #[test]
+    fn mask_chat() {
+        let blocklist = Blocklist::new(["gallows", "noose"]);
+        assert_eq!(
+            blocklist.mask("To the GALLOWS with the nооse!"),
+            "To the ******* with the *****!"
+        );
+        assert_eq!(blocklist.mask("gallowsbird"), "gallowsbird");
+    }
+}
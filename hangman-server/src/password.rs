@@ -0,0 +1,50 @@
+//! Hashing of game passwords
+//!
+//! Hashing is slow on purpose, so it runs on the blocking thread pool.
+
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use rand::rngs::OsRng;
+use thiserror::Error;
+use tracing::warn;
+
+#[derive(Debug, Error)]
+pub enum PasswordError {
+    #[error("failed to hash password: {0}")]
+    Hash(argon2::password_hash::Error),
+    #[error("hashing task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+pub async fn hash_password(password: String) -> Result<String, PasswordError> {
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|h| h.to_string())
+            .map_err(PasswordError::Hash)
+    })
+    .await?
+}
+
+pub async fn verify_password(hash: String, password: String) -> bool {
+    let res = tokio::task::spawn_blocking(move || {
+        let hash = PasswordHash::new(&hash)?;
+        Argon2::default().verify_password(password.as_bytes(), &hash)
+    })
+    .await;
+    match res {
+        Ok(Ok(())) => true,
+        Ok(Err(argon2::password_hash::Error::Password)) => false,
+        Ok(Err(e)) => {
+            warn!("failed to verify password: {e}");
+            false
+        }
+        Err(e) => {
+            warn!("password verification task failed: {e}");
+            false
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Rate limiting of client messages
+
+use crate::config::RateLimitConfig;
+use std::time::{Duration, Instant};
+
+/// Token bucket that is refilled continuously
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32, refill_per_sec: f64, now: Instant) -> Self {
+        Self {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            refill_per_sec,
+            last_refill: now,
+        }
+    }
+
+    fn try_take(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Time until the next token is available
+    fn retry_after(&self) -> Duration {
+        Duration::try_from_secs_f64(((1.0 - self.tokens) / self.refill_per_sec).max(0.0))
+            .unwrap_or(Duration::MAX)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RateLimit {
+    Allowed,
+    /// The message must be dropped, the next one is allowed after the given time
+    Dropped(Duration),
+    /// Too many messages were dropped, the client should be disconnected
+    Exceeded,
+}
+
+/// Rate limiter for the messages of a single connection
+#[derive(Debug)]
+pub struct RateLimiter {
+    bucket: TokenBucket,
+    max_dropped: u32,
+    dropped_window: Duration,
+    dropped: u32,
+    window_start: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self::new_at(config, Instant::now())
+    }
+
+    fn new_at(config: &RateLimitConfig, now: Instant) -> Self {
+        Self {
+            bucket: TokenBucket::new(config.burst, config.refill_per_sec, now),
+            max_dropped: config.max_dropped,
+            dropped_window: Duration::from_secs(config.dropped_window_secs),
+            dropped: 0,
+            window_start: now,
+        }
+    }
+
+    pub fn check(&mut self) -> RateLimit {
+        self.check_at(Instant::now())
+    }
+
+    fn check_at(&mut self, now: Instant) -> RateLimit {
+        if self.bucket.try_take(now) {
+            return RateLimit::Allowed;
+        }
+        if now.saturating_duration_since(self.window_start) > self.dropped_window {
+            self.window_start = now;
+            self.dropped = 0;
+        }
+        self.dropped += 1;
+        if self.dropped > self.max_dropped {
+            RateLimit::Exceeded
+        } else {
+            RateLimit::Dropped(self.bucket.retry_after())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: RateLimitConfig = RateLimitConfig {
+        refill_per_sec: 2.0,
+        burst: 3,
+        max_dropped: 2,
+        dropped_window_secs: 10,
+    };
+
+    #[test]
+    fn burst_and_refill() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new_at(&CONFIG, start);
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(start), RateLimit::Allowed);
+        }
+        assert_eq!(
+            limiter.check_at(start),
+            RateLimit::Dropped(Duration::from_millis(500))
+        );
+
+        // One token after half a second
+        let later = start + Duration::from_millis(500);
+        assert_eq!(limiter.check_at(later), RateLimit::Allowed);
+        assert!(matches!(limiter.check_at(later), RateLimit::Dropped(_)));
+
+        // Never more than the burst size
+        let much_later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(much_later), RateLimit::Allowed);
+        }
+        assert!(matches!(
+            limiter.check_at(much_later),
+            RateLimit::Dropped(_)
+        ));
+    }
+
+    #[test]
+    fn exceeded() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new_at(&CONFIG, start);
+        for _ in 0..3 {
+            limiter.check_at(start);
+        }
+        assert!(matches!(limiter.check_at(start), RateLimit::Dropped(_)));
+        assert!(matches!(limiter.check_at(start), RateLimit::Dropped(_)));
+        assert_eq!(limiter.check_at(start), RateLimit::Exceeded);
+    }
+
+    #[test]
+    fn dropped_messages_expire() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new_at(&CONFIG, start);
+        for _ in 0..3 {
+            limiter.check_at(start);
+        }
+        limiter.check_at(start);
+        limiter.check_at(start);
+
+        // A new window starts, but the bucket is refilled by then
+        let later = start + Duration::from_secs(11);
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(later), RateLimit::Allowed);
+        }
+        assert!(matches!(limiter.check_at(later), RateLimit::Dropped(_)));
+        assert!(matches!(limiter.check_at(later), RateLimit::Dropped(_)));
+        assert_eq!(limiter.check_at(later), RateLimit::Exceeded);
+    }
+}
@@ -1,115 +1,348 @@
-use crate::config::HangmanConfig;
-use hangman_data::{Difficulty, GameLanguage, GameSettings};
+use crate::{
+    languages::{Languages, LanguagesError},
+    moderation::{Blocklist, Blocklists},
+    word_generator::custom::CustomWordlist,
+};
+use arc_swap::ArcSwap;
+use hangman_data::{
+    wordlist::{self, CaseRules, FormatError},
+    Difficulty, GameLanguage, GameSettings,
+};
 use once_cell::sync::OnceCell;
 use rand::Rng;
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use thiserror::Error;
 use tokio::{fs, io};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-pub static GENERATOR: OnceCell<WordGenerator> = OnceCell::new();
+pub mod custom;
+mod difficulty;
+pub mod reload;
 
-pub async fn generate_word(settings: &GameSettings) -> String {
+/// Replaced when the wordlists are reloaded
+pub static GENERATOR: OnceCell<ArcSwap<WordGenerator>> = OnceCell::new();
+
+/// The current word generator
+pub fn generator() -> Arc<WordGenerator> {
     GENERATOR
         .get()
         .expect("generator not initialized")
-        .generate(&settings.language, &settings.difficulty)
-        .await
-        .expect("failed to generate word")
+        .load_full()
+}
+
+/// Generates a word the game didn't use yet, from its custom wordlist if it has one
+pub fn generate_word(
+    settings: &GameSettings,
+    custom_words: Option<&CustomWordlist>,
+    used: &mut UsedWords,
+) -> Result<String, GeneratorError> {
+    if let Some(custom_words) = custom_words {
+        return Ok(custom_words.random_word(used));
+    }
+    generator().generate(&settings.language, &settings.difficulty, used)
 }
 
+/// Random words that are tried before all unused words are searched
+const PICK_ATTEMPTS: usize = 32;
+
+/// Words a game already used
+///
+/// Words are folded with the case rules of the game's language, like guesses,
+/// so a word isn't used again in another case.
+#[derive(Debug)]
+pub struct UsedWords {
+    words: HashSet<String>,
+    case: CaseRules,
+}
+
+impl UsedWords {
+    pub fn new(case: CaseRules) -> Self {
+        Self {
+            words: HashSet::new(),
+            case,
+        }
+    }
+
+    /// Picks a random word out of `len` candidates that wasn't used yet and marks it as used
+    ///
+    /// Random candidates are tried first, only if they were all used, which is likely once
+    /// most of the candidates were used, all candidates are searched.
+    fn pick<'a>(&mut self, len: usize, candidate: impl Fn(usize) -> &'a str) -> Option<&'a str> {
+        if len == 0 {
+            return None;
+        }
+        let mut rng = rand::thread_rng();
+        let is_unused = |w: &&str| !self.words.contains(&self.case.fold(w));
+        let word = (0..PICK_ATTEMPTS)
+            .map(|_| candidate(rng.gen_range(0..len)))
+            .find(is_unused)
+            .or_else(|| {
+                let unused: Vec<&str> = (0..len).map(&candidate).filter(is_unused).collect();
+                (!unused.is_empty()).then(|| unused[rng.gen_range(0..unused.len())])
+            })?;
+        self.words.insert(self.case.fold(word));
+        Some(word)
+    }
+
+    /// Allows all words again
+    fn clear(&mut self) {
+        self.words.clear();
+    }
+}
+
+/// How guesses are compared in this language
+pub fn case_rules(lang: &GameLanguage) -> CaseRules {
+    generator()
+        .wordlists
+        .get(lang)
+        .map(|w| w.case)
+        .unwrap_or_default()
+}
+
+/// Generates random words from wordlists that are kept in memory
 #[derive(Debug)]
 pub struct WordGenerator {
-    wordlists_dir: String,
-    limits: HashMap<GameLanguage, usize>,
+    languages: Languages,
+    wordlists: HashMap<GameLanguage, Wordlist>,
+    /// Loaded together with the wordlists, so every language has its blocklist
+    blocklists: Blocklists,
+}
+
+#[derive(Debug)]
+struct Wordlist {
+    /// Ordered by how common the words are
+    words: Vec<Box<str>>,
+    /// Indices of the words of every difficulty
+    difficulties: HashMap<Difficulty, Vec<u32>>,
+    case: CaseRules,
+}
+
+impl Wordlist {
+    /// `occurrences` of every word, if the wordlist has them
+    fn new(words: Vec<Box<str>>, occurrences: Option<&[u64]>, case: CaseRules) -> Self {
+        let difficulties = difficulty::classify(&words, occurrences, case);
+        Self {
+            words,
+            difficulties,
+            case,
+        }
+    }
+
+    /// Generates a word that wasn't used yet
+    ///
+    /// If all words of the difficulty were used, words of the closest difficulties are used.
+    /// If every word was used, all words are allowed again.
+    fn generate(&self, difficulty: &Difficulty, used: &mut UsedWords) -> &str {
+        for band in difficulty::widening(difficulty) {
+            let Some(indices) = self.difficulties.get(&band) else {
+                continue;
+            };
+            if let Some(word) = used.pick(indices.len(), |i| &self.words[indices[i] as usize]) {
+                if band != *difficulty {
+                    debug!("all {difficulty} words were used, using a {band} word");
+                }
+                return word;
+            }
+        }
+        // Random, or also words outside the length bounds of all difficulties
+        if let Some(word) = used.pick(self.words.len(), |i| &self.words[i]) {
+            return word;
+        }
+        info!("all words were used, starting over");
+        used.clear();
+        used.pick(self.words.len(), |i| &self.words[i])
+            .expect("wordlist is empty")
+    }
+
+    /// Bytes used by the words and the indices
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self.words.as_slice())
+            + self.words.iter().map(|w| w.len()).sum::<usize>()
+            + self
+                .difficulties
+                .values()
+                .map(|i| std::mem::size_of_val(i.as_slice()))
+                .sum::<usize>()
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum GeneratorError {
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
-    #[error("this language was not preprocessed")]
-    LanguageNotPreprocessed,
+    #[error("{0}")]
+    Languages(#[from] LanguagesError),
+    #[error("the wordlist of this language was not loaded")]
+    LanguageNotLoaded,
+    #[error("the wordlist of {0} has no words in its alphabet")]
+    EmptyWordlist(GameLanguage),
+    #[error("invalid wordlist of {0}: {1}")]
+    InvalidWordlist(GameLanguage, FormatError),
+    #[error("word generation stopped")]
+    Stopped,
 }
 
 impl WordGenerator {
-    pub async fn preprocess(
-        config: &HangmanConfig,
-        languages: &[GameLanguage],
-    ) -> Result<Self, GeneratorError> {
-        let mut limits = HashMap::new();
-
-        debug!("preprocessing wordlists");
-        for lang in languages {
-            let file = fs::read_to_string(wordlist_path_for_language(&config.wordlists_dir, lang))
+    /// Reads the language manifest and the wordlists and blocklists of all languages into memory
+    pub async fn load(wordlists_dir: &str, blocklists_dir: &str) -> Result<Self, GeneratorError> {
+        let languages = Languages::load(wordlists_dir)?;
+        let mut wordlists = HashMap::new();
+
+        debug!("loading wordlists");
+        for lang in languages.iter() {
+            let file = fs::read_to_string(lang.wordlist_path(wordlists_dir))
                 .await
                 .map_err(GeneratorError::Io)?;
-            let n = file.lines().count();
+            let entries = wordlist::parse(&file)
+                .map_err(|e| GeneratorError::InvalidWordlist(lang.code.clone(), e))?;
+            let (entries, skipped): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| {
+                lang.is_valid_word(e.word)
+                    && !e
+                        .category
+                        .is_some_and(|c| lang.exclude_categories.iter().any(|e| e == c))
+            });
+            if entries.is_empty() {
+                return Err(GeneratorError::EmptyWordlist(lang.code.clone()));
+            }
+            if !skipped.is_empty() {
+                debug!(
+                    "skipped {} words for {} that are not in the alphabet or excluded",
+                    skipped.len(),
+                    lang.code
+                );
+            }
+            let occurrences: Option<Vec<u64>> = entries.iter().map(|e| e.occurrences).collect();
+            if occurrences.is_none() {
+                warn!(
+                    "wordlist for {} is missing occurrences, difficulties are estimated from the \
+                     position of the words, preprocess the wordlist again to fix this",
+                    lang.code
+                );
+            }
+            let wordlist = Wordlist::new(
+                entries.iter().map(|e| Box::from(e.word)).collect(),
+                occurrences.as_deref(),
+                lang.case,
+            );
 
-            debug!("finished preprocessing for {lang}: {n}");
-            limits.insert(lang.clone(), n);
+            info!(
+                "loaded {} words for {}, using {} KiB",
+                wordlist.words.len(),
+                lang.code,
+                wordlist.memory_usage() / 1024
+            );
+            for difficulty in Difficulty::all() {
+                if let Some(indices) = wordlist.difficulties.get(&difficulty) {
+                    debug!("{}, {difficulty}: {} words", lang.code, indices.len());
+                }
+            }
+            wordlists.insert(lang.code.clone(), wordlist);
         }
 
-        debug!("preprocessing finished");
+        let blocklists = Blocklists::load(blocklists_dir, &languages).await?;
+
+        debug!("loading finished");
         Ok(Self {
-            wordlists_dir: config.wordlists_dir.clone(),
-            limits,
+            languages,
+            wordlists,
+            blocklists,
         })
     }
 
-    pub async fn generate(
+    pub fn languages(&self) -> &Languages {
+        &self.languages
+    }
+
+    /// Blocked words of a language, empty if the language has no blocklist
+    pub fn blocklist(&self, lang: &GameLanguage) -> Arc<Blocklist> {
+        self.blocklists.get(lang)
+    }
+
+    pub fn generate(
         &self,
         lang: &GameLanguage,
         difficulty: &Difficulty,
+        used: &mut UsedWords,
     ) -> Result<String, GeneratorError> {
-        let words = self
-            .limits
+        let wordlist = self
+            .wordlists
             .get(lang)
-            .ok_or(GeneratorError::LanguageNotPreprocessed)?;
-        let range = match difficulty {
-            Difficulty::Random => 0..*words,
-            _ => {
-                let diffs = vec![
-                    Difficulty::Easy,
-                    Difficulty::Medium,
-                    Difficulty::Hard,
-                    Difficulty::Insane,
-                ];
-                let n = diffs
-                    .iter()
-                    .enumerate()
-                    .find(|(_, d)| *d == difficulty)
-                    .unwrap()
-                    .0;
-                let frac = words / diffs.len();
-                (n * frac)..((n + 1) * frac)
-            }
-        };
-
-        debug!("choosing random word in range {range:?} for {lang}, {difficulty}");
-
-        let s = fs::read_to_string(wordlist_path_for_language(&self.wordlists_dir, lang))
-            .await
-            .map_err(GeneratorError::Io)?
-            .lines()
-            .nth(rand::thread_rng().gen_range(range))
-            .expect("random number too high")
-            .to_string();
+            .ok_or(GeneratorError::LanguageNotLoaded)?;
+        let s = wordlist.generate(difficulty, used).to_string();
         info!("generated random word for {lang}: {s}");
         Ok(s)
     }
 }
 
-fn wordlist_path_for_language(wordlists_dir: &str, lang: &GameLanguage) -> PathBuf {
-    let mut path = PathBuf::new();
-    path.push(wordlists_dir);
-    match lang {
-        GameLanguage::English => path.push("eng-com_web-public_2018_1M-words.pre.txt"),
-        GameLanguage::Spanish => path.push("spa_web_2016_1M-words.pre.txt"),
-        GameLanguage::French => path.push("fra_mixed_2009_1M-words.pre.txt"),
-        GameLanguage::German => path.push("deu-de_web_2021_1M-words.pre.txt"),
-        GameLanguage::Russian => path.push("rus-ru_web-public_2019_1M-words.pre.txt"),
-        GameLanguage::Turkish => path.push("tur-tr_web_2019_1M-words.pre.txt"),
-    }
-    path
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wordlist(words: &[&str]) -> Wordlist {
+        Wordlist::new(
+            words.iter().map(|w| Box::from(*w)).collect(),
+            None,
+            CaseRules::default(),
+        )
+    }
+
+    #[test]
+    fn no_repeated_words() {
+        let wordlist = wordlist(&[
+            "house", "garden", "water", "mother", "table", "window", "jazzy", "quack",
+        ]);
+        let mut used = UsedWords::new(CaseRules::default());
+        let mut words: Vec<&str> = (0..8)
+            .map(|_| wordlist.generate(&Difficulty::Easy, &mut used))
+            .collect();
+        words.sort();
+        words.dedup();
+        assert_eq!(words.len(), 8);
+    }
+
+    #[test]
+    fn exhausted_wordlist() {
+        let wordlist = wordlist(&["house", "garden", "water", "mother"]);
+        let mut used = UsedWords::new(CaseRules::default());
+        for _ in 0..4 {
+            wordlist.generate(&Difficulty::Insane, &mut used);
+        }
+        // Starts over instead of failing
+        wordlist.generate(&Difficulty::Insane, &mut used);
+        assert_eq!(used.words.len(), 1);
+    }
+
+    #[test]
+    fn case_variants_are_used_once() {
+        let wordlist = wordlist(&["about", "About", "house"]);
+        let mut used = UsedWords::new(CaseRules::default());
+        let words: HashSet<String> = (0..2)
+            .map(|_| {
+                wordlist
+                    .generate(&Difficulty::Easy, &mut used)
+                    .to_lowercase()
+            })
+            .collect();
+        assert_eq!(
+            words,
+            HashSet::from(["about".to_string(), "house".to_string()])
+        );
+    }
+
+    #[test]
+    fn widening() {
+        assert_eq!(
+            difficulty::widening(&Difficulty::Medium),
+            vec![
+                Difficulty::Medium,
+                Difficulty::Easy,
+                Difficulty::Hard,
+                Difficulty::Insane
+            ]
+        );
+        assert!(difficulty::widening(&Difficulty::Random).is_empty());
+    }
 }
@@ -0,0 +1,129 @@
+//! Wordlists that are supplied by the creator of a game
+
+use crate::{config::CustomWordsConfig, word_generator::UsedWords};
+use std::collections::HashSet;
+use thiserror::Error;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum WordlistError {
+    #[error("the wordlist must contain at least {0} different words")]
+    TooFew(usize),
+    #[error("the wordlist must not contain more than {0} words")]
+    TooMany(usize),
+    #[error("{0:?} is longer than {1} letters")]
+    TooLong(String, usize),
+    #[error("{0:?} must only contain letters")]
+    InvalidCharacters(String),
+}
+
+/// Validated wordlist of a single game, all words are equally likely
+#[derive(Debug)]
+pub struct CustomWordlist {
+    words: Vec<Box<str>>,
+}
+
+impl CustomWordlist {
+    /// Normalizes the words, removes empty lines and duplicates and checks the limits
+    pub fn new(words: Vec<String>, config: &CustomWordsConfig) -> Result<Self, WordlistError> {
+        let mut seen = HashSet::new();
+        let mut unique = vec![];
+        for word in words {
+            let word: String = word.trim().nfc().collect();
+            if word.is_empty() {
+                continue;
+            }
+            if !word
+                .graphemes(true)
+                .all(|g| g.chars().next().is_some_and(char::is_alphabetic))
+            {
+                return Err(WordlistError::InvalidCharacters(word));
+            }
+            if word.graphemes(true).count() > config.max_word_len {
+                return Err(WordlistError::TooLong(word, config.max_word_len));
+            }
+            if seen.insert(word.to_lowercase()) {
+                unique.push(word.into_boxed_str());
+            }
+            if unique.len() > config.max_words {
+                return Err(WordlistError::TooMany(config.max_words));
+            }
+        }
+        if unique.len() < config.min_words {
+            return Err(WordlistError::TooFew(config.min_words));
+        }
+        Ok(Self { words: unique })
+    }
+
+    /// A random word that wasn't used yet, all words are allowed again once every word was used
+    pub fn random_word(&self, used: &mut UsedWords) -> String {
+        if let Some(word) = used.pick(self.words.len(), |i| &self.words[i]) {
+            return word.to_string();
+        }
+        used.clear();
+        used.pick(self.words.len(), |i| &self.words[i])
+            .expect("wordlist is empty")
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hangman_data::wordlist::CaseRules;
+
+    const CONFIG: CustomWordsConfig = CustomWordsConfig {
+        min_words: 2,
+        max_words: 4,
+        max_word_len: 8,
+    };
+
+    fn wordlist(words: &[&str]) -> Result<CustomWordlist, WordlistError> {
+        CustomWordlist::new(words.iter().map(|w| w.to_string()).collect(), &CONFIG)
+    }
+
+    #[test]
+    fn dedup() {
+        let list = wordlist(&[" Rust ", "", "rust", "Cargo", "cafe\u{301}"]).unwrap();
+        assert_eq!(
+            list.words,
+            vec![Box::from("Rust"), Box::from("Cargo"), Box::from("café")]
+        );
+    }
+
+    #[test]
+    fn random_words() {
+        let list = wordlist(&["rust", "cargo", "crate"]).unwrap();
+        let mut used = UsedWords::new(CaseRules::default());
+        let mut words: Vec<String> = (0..3).map(|_| list.random_word(&mut used)).collect();
+        words.sort();
+        assert_eq!(words, vec!["cargo", "crate", "rust"]);
+        // All words were used, so they are allowed again
+        list.random_word(&mut used);
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(
+            wordlist(&["rust", "RUST"]).unwrap_err(),
+            WordlistError::TooFew(2)
+        );
+        assert_eq!(
+            wordlist(&["a", "b", "c", "d", "e"]).unwrap_err(),
+            WordlistError::TooMany(4)
+        );
+        assert_eq!(
+            wordlist(&["rust", "rustacean"]).unwrap_err(),
+            WordlistError::TooLong("rustacean".to_string(), 8)
+        );
+        assert_eq!(
+            wordlist(&["rust", "c++"]).unwrap_err(),
+            WordlistError::InvalidCharacters("c++".to_string())
+        );
+        assert_eq!(
+            wordlist(&["rust", "two words"]).unwrap_err(),
+            WordlistError::InvalidCharacters("two words".to_string())
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Estimates how hard words are to guess
+//!
+//! Every word gets a score from its features and the words are split into quartiles by that
+//! score, the easiest quartile is [`Difficulty::Easy`].
+
+use hangman_data::{wordlist::CaseRules, Difficulty};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::RangeInclusive,
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Difficulties that are based on the score, from easy to hard
+const SCORED: [Difficulty; 4] = [
+    Difficulty::Easy,
+    Difficulty::Medium,
+    Difficulty::Hard,
+    Difficulty::Insane,
+];
+
+/// Weights of the normalized word features in the score
+const RARITY_WEIGHT: f64 = 2.0;
+const OBSCURITY_WEIGHT: f64 = 2.0;
+const FEW_DISTINCT_WEIGHT: f64 = 1.0;
+const SHORTNESS_WEIGHT: f64 = 1.0;
+
+/// The difficulty and then the other difficulties from the closest to the farthest
+///
+/// Empty for [`Difficulty::Random`].
+pub fn widening(difficulty: &Difficulty) -> Vec<Difficulty> {
+    let Some(start) = SCORED.iter().position(|d| d == difficulty) else {
+        return vec![];
+    };
+    let mut bands = SCORED.to_vec();
+    // Stable, so easier difficulties come first at the same distance
+    bands.sort_by_key(|d| {
+        let i = SCORED.iter().position(|s| s == d).unwrap_or_default();
+        i.abs_diff(start)
+    });
+    bands
+}
+
+/// Number of letters a word of this difficulty can have
+fn length_bounds(difficulty: &Difficulty) -> RangeInclusive<usize> {
+    match difficulty {
+        Difficulty::Random => 1..=usize::MAX,
+        Difficulty::Easy => 5..=12,
+        Difficulty::Medium => 4..=12,
+        Difficulty::Hard => 4..=14,
+        Difficulty::Insane => 4..=usize::MAX,
+    }
+}
+
+/// Raw features of a word, a higher value means harder for all of them
+struct Features {
+    /// Average information content of the distinct letters, rare letters are hard to guess
+    rarity: f64,
+    /// Negative logarithm of the occurrences, or logarithm of the position in the wordlist if
+    /// they are unknown, uncommon words are hard to guess
+    obscurity: f64,
+    /// Fewer distinct letters means fewer correct guesses
+    few_distinct: f64,
+    /// Short words reveal less
+    shortness: f64,
+}
+
+/// Groups the indices of the words by difficulty
+///
+/// `words` must be ordered by how common they are, the most common first.
+/// Words that are outside the length bounds of their difficulty are left out.
+/// Letters are compared like guesses, with the case rules of the language.
+pub fn classify(
+    words: &[Box<str>],
+    occurrences: Option<&[u64]>,
+    case: CaseRules,
+) -> HashMap<Difficulty, Vec<u32>> {
+    let letters: Vec<Vec<String>> = words
+        .iter()
+        .map(|w| w.graphemes(true).map(|l| case.fold(l)).collect())
+        .collect();
+
+    let mut letter_counts: HashMap<&str, usize> = HashMap::new();
+    for letter in letters.iter().flatten() {
+        *letter_counts.entry(letter).or_default() += 1;
+    }
+    let total = letter_counts.values().sum::<usize>() as f64;
+
+    let features: Vec<Features> = letters
+        .iter()
+        .enumerate()
+        .map(|(i, letters)| {
+            let distinct: HashSet<&str> = letters.iter().map(String::as_str).collect();
+            let rarity = distinct
+                .iter()
+                .map(|l| -(letter_counts[l] as f64 / total).log2())
+                .sum::<f64>()
+                / distinct.len().max(1) as f64;
+            Features {
+                rarity,
+                obscurity: match occurrences {
+                    Some(o) => -(o[i].max(1) as f64).ln(),
+                    None => ((i + 1) as f64).ln(),
+                },
+                few_distinct: -(distinct.len() as f64),
+                shortness: -(letters.len() as f64),
+            }
+        })
+        .collect();
+
+    let rarity = normalizer(features.iter().map(|f| f.rarity));
+    let obscurity = normalizer(features.iter().map(|f| f.obscurity));
+    let few_distinct = normalizer(features.iter().map(|f| f.few_distinct));
+    let shortness = normalizer(features.iter().map(|f| f.shortness));
+    let scores: Vec<f64> = features
+        .iter()
+        .map(|f| {
+            RARITY_WEIGHT * rarity(f.rarity)
+                + OBSCURITY_WEIGHT * obscurity(f.obscurity)
+                + FEW_DISTINCT_WEIGHT * few_distinct(f.few_distinct)
+                + SHORTNESS_WEIGHT * shortness(f.shortness)
+        })
+        .collect();
+
+    let mut by_score: Vec<usize> = (0..words.len()).collect();
+    by_score.sort_by(|a, b| scores[*a].total_cmp(&scores[*b]));
+
+    let mut classes: HashMap<Difficulty, Vec<u32>> = HashMap::new();
+    for (rank, i) in by_score.into_iter().enumerate() {
+        let difficulty = &SCORED[rank * SCORED.len() / words.len()];
+        if length_bounds(difficulty).contains(&letters[i].len()) {
+            classes
+                .entry(difficulty.clone())
+                .or_default()
+                .push(i as u32);
+        }
+    }
+    classes
+}
+
+/// Maps values linearly to `0..=1`, based on the minimum and maximum of all values
+fn normalizer(values: impl Iterator<Item = f64>) -> impl Fn(f64) -> f64 {
+    let (min, max) = values.fold((f64::MAX, f64::MIN), |(min, max), v| {
+        (min.min(v), max.max(v))
+    });
+    move |v| {
+        if max > min {
+            (v - min) / (max - min)
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<Box<str>> {
+        words.iter().map(|w| Box::from(*w)).collect()
+    }
+
+    #[test]
+    fn length_guards() {
+        let words = words(&[
+            "the",
+            "a",
+            "house",
+            "garden",
+            "water",
+            "mother",
+            "i",
+            "table",
+            "jazz",
+            "quiz",
+            "rhythm",
+            "onomatopoeia",
+        ]);
+        let classes = classify(&words, None, CaseRules::default());
+        for (difficulty, indices) in &classes {
+            for i in indices {
+                let len = words[*i as usize].len();
+                assert!(
+                    length_bounds(difficulty).contains(&len),
+                    "{} is {difficulty}",
+                    words[*i as usize]
+                );
+            }
+        }
+        let easy = &classes[&Difficulty::Easy];
+        assert!(!easy.contains(&0) && !easy.contains(&1) && !easy.contains(&6));
+    }
+
+    #[test]
+    fn rare_letters_are_harder() {
+        let words = words(&[
+            "seats", "tease", "state", "taste", "eases", "sates", "jazzy", "quack", "fjord",
+            "kiwis",
+        ]);
+        let classes = classify(&words, None, CaseRules::default());
+        let easy = &classes[&Difficulty::Easy];
+        let insane = &classes[&Difficulty::Insane];
+        assert!(easy.iter().all(|i| *i < 6));
+        assert!(insane.iter().all(|i| *i >= 6));
+    }
+}
@@ -0,0 +1,94 @@
+//! Reloading of the wordlists while the server is running
+//!
+//! Running games pick up the new wordlists for their next word.
+
+use crate::{
+    config::HangmanConfig,
+    word_generator::{GeneratorError, WordGenerator, GENERATOR},
+};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::{path::Path, sync::Arc, time::Duration};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Time to wait for more changes before reloading, files are often written in multiple steps
+const RELOAD_DELAY: Duration = Duration::from_secs(1);
+
+/// Loads the wordlists and blocklists again, the current ones are only replaced if all of them
+/// are valid
+pub async fn reload(config: &HangmanConfig) -> Result<(), GeneratorError> {
+    let generator =
+        WordGenerator::load(&config.wordlists_dir, &config.moderation.blocklists_dir).await?;
+    GENERATOR
+        .get()
+        .expect("generator not initialized")
+        .store(Arc::new(generator));
+    info!("reloaded wordlists");
+    Ok(())
+}
+
+/// Reloads the wordlists on SIGHUP and, if `watch` is set, when files in the wordlists
+/// directory change
+pub fn spawn_reloader(config: Arc<HangmanConfig>) {
+    let (tx, mut rx) = mpsc::channel(1);
+    let watcher = config
+        .watch_wordlists
+        .then(|| watch_dir(&config.wordlists_dir, tx.clone()))
+        .flatten();
+    #[cfg(unix)]
+    spawn_sighup_listener(tx);
+
+    tokio::spawn(async move {
+        // Watching stops when the watcher is dropped
+        let _watcher = watcher;
+        while rx.recv().await.is_some() {
+            tokio::time::sleep(RELOAD_DELAY).await;
+            while rx.try_recv().is_ok() {}
+            if let Err(e) = reload(&config).await {
+                warn!("failed to reload wordlists, keeping the current ones: {e}");
+            }
+        }
+    });
+}
+
+fn watch_dir(wordlists_dir: &str, tx: mpsc::Sender<()>) -> Option<RecommendedWatcher> {
+    let watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) if !event.kind.is_access() => {
+                debug!("wordlists changed: {:?}", event.paths);
+                // A reload is already pending if the channel is full
+                let _ = tx.try_send(());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("failed to watch wordlists: {e}"),
+        });
+    match watcher.and_then(|mut w| {
+        w.watch(Path::new(wordlists_dir), RecursiveMode::NonRecursive)?;
+        Ok(w)
+    }) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            warn!("failed to watch wordlists, they are only reloaded on request: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(unix)]
+fn spawn_sighup_listener(tx: mpsc::Sender<()>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    tokio::spawn(async move {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("failed to listen for SIGHUP: {e}");
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            info!("received SIGHUP, reloading wordlists");
+            let _ = tx.try_send(());
+        }
+    });
+}
@@ -0,0 +1,213 @@
+use crate::{
+    components::{
+        CenterContainer, Error, LanguageSelect, MaterialButton, MaterialLinkButton, TopBar,
+    },
+    create_user::CreateUser,
+    global_state::USER,
+    urls,
+    urls::UrlError,
+};
+use dioxus::prelude::*;
+use dioxus_material_icons::{MaterialIcon, MaterialIconColor};
+use dioxus_router::use_router;
+use fermi::use_read;
+use hangman_data::{Difficulty, GameCode, GameMode, GameSettings, LobbyInfo, QuickMatchBody};
+use log::info;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+enum BrowseGamesError {
+    #[error("failed to retrieve url: {0}")]
+    UrlError(#[from] UrlError),
+    #[error("{0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("{0}")]
+    Rejected(String),
+}
+
+pub fn BrowseGames(cx: Scope) -> Element {
+    let router = use_router(cx);
+    let client = cx.use_hook(reqwest::Client::new);
+    let error = use_state(cx, || Option::<BrowseGamesError>::None);
+    let user = use_read(cx, USER);
+    // Preferred settings, used to filter the list and for quick matches
+    let settings = use_state(cx, GameSettings::default);
+
+    let games = use_future(cx, (), |_| {
+        to_owned![client];
+        async move {
+            let origin = urls::http_url_origin()?;
+            let games = client
+                .get(format!("{origin}/api/games"))
+                .send()
+                .await?
+                .json::<Vec<LobbyInfo>>()
+                .await?;
+            Ok::<_, BrowseGamesError>(games)
+        }
+    });
+
+    match (user, error.get()) {
+        (Ok(Some(user)), None) => {
+            let on_quickmatch = move |_| match urls::http_url_origin() {
+                Ok(origin) => {
+                    let body = QuickMatchBody {
+                        token: user.token,
+                        settings: settings.get().clone(),
+                    };
+                    to_owned![router, client, error];
+                    cx.spawn(async move {
+                        match client
+                            .post(format!("{origin}/api/quickmatch"))
+                            .json(&body)
+                            .send()
+                            .await
+                        {
+                            Ok(res) if !res.status().is_success() => match res.text().await {
+                                Ok(reason) => error.set(Some(BrowseGamesError::Rejected(reason))),
+                                Err(e) => error.set(Some(e.into())),
+                            },
+                            Ok(res) => match res.json::<GameCode>().await {
+                                Ok(code) => {
+                                    info!("quick match in game {code}");
+                                    router.navigate_to(&format!("/game/{code}"));
+                                }
+                                Err(e) => error.set(Some(e.into())),
+                            },
+                            Err(e) => error.set(Some(e.into())),
+                        }
+                    });
+                }
+                Err(e) => error.set(Some(e.into())),
+            };
+
+            let list = match games.value() {
+                None => rsx!(div { class: "race-by" }),
+                Some(Err(e)) => rsx!(p {
+                    class: "font-light text-center",
+                    "Failed to load games: {e}"
+                }),
+                Some(Ok(games)) => {
+                    let s = settings.get();
+                    let matching: Vec<_> = games
+                        .iter()
+                        .filter(|g| {
+                            g.settings.mode == s.mode
+                                && g.settings.language == s.language
+                                && g.settings.difficulty == s.difficulty
+                        })
+                        .collect();
+                    if matching.is_empty() {
+                        rsx!(p {
+                            class: "font-light text-center",
+                            "No public games found"
+                        })
+                    } else {
+                        rsx!(ul {
+                            class: "flex flex-col gap-1 max-h-64 overflow-y-auto",
+                            matching.into_iter().map(|g| rsx!(LobbyRow { lobby: g.clone() }))
+                        })
+                    }
+                }
+            };
+
+            cx.render(rsx!(
+                CenterContainer {
+                    div {
+                        class: "bg-zinc-800 rounded-xl shadow-lg w-96 max-w-[80%]",
+                        TopBar {
+                            MaterialLinkButton { name: "arrow_back", to: "/" }
+                            span {
+                                class: "font-light",
+                                "Browse Games"
+                            }
+                            MaterialButton { name: "refresh", onclick: move |_| games.restart() }
+                        }
+                        div {
+                            class: "p-6 flex flex-col gap-2",
+                            div {
+                                class: "flex gap-1",
+                                select {
+                                    class: "input p-1 w-full rounded",
+                                    onchange: move |e| {
+                                        if let Ok(mode) = serde_json::from_str::<GameMode>(&e.value) {
+                                            settings.set(GameSettings { mode, ..settings.get().clone() });
+                                        }
+                                    },
+                                    GameMode::all().iter().map(|m| {
+                                        let value = serde_json::to_string(&m).expect("failed to serialize game mode");
+                                        rsx!(option { value: "{value}", "{m}" })
+                                    })
+                                }
+                                LanguageSelect {
+                                    onchange: move |language| {
+                                        settings.set(GameSettings { language, ..settings.get().clone() });
+                                    },
+                                }
+                                select {
+                                    class: "input p-1 w-full rounded",
+                                    onchange: move |e| {
+                                        if let Ok(difficulty) = serde_json::from_str::<Difficulty>(&e.value) {
+                                            settings.set(GameSettings { difficulty, ..settings.get().clone() });
+                                        }
+                                    },
+                                    Difficulty::all().iter().map(|d| {
+                                        let is_default = *d == Difficulty::default();
+                                        let value = serde_json::to_string(&d).expect("failed to serialize difficulty");
+                                        rsx!(option { value: "{value}", selected: is_default, "{d}" })
+                                    })
+                                }
+                            }
+                            list
+                            button {
+                                class: "button",
+                                onclick: on_quickmatch,
+                                "Quick Match"
+                            }
+                        }
+                    }
+                }
+            ))
+        }
+        (Ok(None), _) => cx.render(rsx!(CreateUser {})),
+        (Err(e), _) => cx.render(rsx!(Error {
+            title: "Failed to load user",
+            error: e,
+        })),
+        (_, Some(e)) => cx.render(rsx!(Error {
+            title: "Failed to find a game",
+            error: e,
+        })),
+    }
+}
+
+#[inline_props]
+fn LobbyRow(cx: Scope, lobby: LobbyInfo) -> Element {
+    let router = use_router(cx);
+    let LobbyInfo {
+        code,
+        settings,
+        players,
+        password,
+        custom_words,
+    } = lobby;
+
+    cx.render(rsx!(
+        li {
+            button {
+                class: "w-full flex items-center gap-2 px-2 py-1 rounded-md transition-colors hover:bg-zinc-700",
+                onclick: move |_| router.navigate_to(&format!("/game/{code}")),
+                span { class: "font-mono", "{code}" }
+                span { class: "grow font-light", "{settings.mode}" }
+                custom_words.then(|| rsx!(
+                    MaterialIcon { name: "edit_note", color: MaterialIconColor::Light, size: 20 }
+                ))
+                password.then(|| rsx!(
+                    MaterialIcon { name: "lock", color: MaterialIconColor::Light, size: 20 }
+                ))
+                MaterialIcon { name: "people", color: MaterialIconColor::Light, size: 20 }
+                span { class: "font-light", "{players}/{settings.max_players}" }
+            }
+        }
+    ))
+}
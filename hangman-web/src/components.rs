@@ -1,7 +1,9 @@
 mod button;
 mod container;
 mod error;
+mod language_select;
 
 pub use button::*;
 pub use container::*;
 pub use error::*;
+pub use language_select::*;
@@ -0,0 +1,57 @@
+#![allow(non_snake_case)]
+
+use crate::urls;
+use dioxus::prelude::*;
+use hangman_data::{GameLanguage, LanguageInfo};
+use log::error;
+
+#[derive(Props)]
+pub struct LanguageSelectProps<'a> {
+    onchange: Option<EventHandler<'a, GameLanguage>>,
+}
+
+/// All languages the server offers, empty while they are loading or if they failed to load
+pub fn use_languages(cx: &ScopeState) -> Vec<LanguageInfo> {
+    let languages = use_future(cx, (), |_| async move {
+        let origin = urls::http_url_origin().map_err(|e| e.to_string())?;
+        reqwest::Client::new()
+            .get(format!("{origin}/api/languages"))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .json::<Vec<LanguageInfo>>()
+            .await
+            .map_err(|e| e.to_string())
+    });
+
+    match languages.value() {
+        Some(Ok(languages)) => languages.clone(),
+        Some(Err(e)) => {
+            error!("failed to load languages: {e}");
+            vec![]
+        }
+        None => vec![],
+    }
+}
+
+/// Select named `language` with all languages the server offers
+pub fn LanguageSelect<'a>(cx: Scope<'a, LanguageSelectProps<'a>>) -> Element<'a> {
+    let languages = use_languages(cx);
+
+    cx.render(rsx!(
+        select {
+            class: "input p-1 w-full rounded",
+            required: true,
+            name: "language",
+            onchange: move |e| {
+                if let Some(h) = &cx.props.onchange {
+                    h.call(GameLanguage(e.value.clone()));
+                }
+            },
+            languages.iter().map(|l| {
+                let is_default = l.code == GameLanguage::default();
+                rsx!(option { value: "{l.code}", selected: is_default, "{l.name}" })
+            })
+        }
+    ))
+}
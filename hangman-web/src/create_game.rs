@@ -1,5 +1,7 @@
 use crate::{
-    components::{CenterContainer, Error, Form, MaterialButton, MaterialLinkButton, TopBar},
+    components::{
+        CenterContainer, Error, Form, LanguageSelect, MaterialButton, MaterialLinkButton, TopBar,
+    },
     create_user::CreateUser,
     global_state::USER,
     urls,
@@ -21,6 +23,8 @@ enum CreateGameError {
     UrlError(#[from] UrlError),
     #[error("{0}")]
     Reqwest(#[from] reqwest::Error),
+    #[error("{0}")]
+    Rejected(String),
 }
 
 pub fn CreateGame(cx: Scope) -> Element {
@@ -36,19 +40,33 @@ pub fn CreateGame(cx: Scope) -> Element {
                     Form {
                         onsubmit: |e: FormEvent| {
                             let mode = e.data.values.get("mode").and_then(|s| serde_json::from_str::<GameMode>(s).ok());
-                            let lang = e.data.values.get("language").and_then(|s| serde_json::from_str::<GameLanguage>(s).ok());
+                            let lang = e.data.values.get("language").filter(|s| !s.is_empty()).map(|s| GameLanguage(s.clone()));
                             let diff = e.data.values.get("difficulty").and_then(|s| serde_json::from_str::<Difficulty>(s).ok());
-                            if let (Some(mode), Some(language), Some(difficulty)) = (mode, lang, diff) {
+                            let rounds = e.data.values.get("rounds").and_then(|s| s.parse::<u32>().ok());
+                            let max_players = e.data.values.get("max_players").and_then(|s| s.parse::<u32>().ok());
+                            let password = e.data.values.get("password").filter(|p| !p.is_empty()).cloned();
+                            let public = e.data.values.get("public").is_some_and(|v| v == "true");
+                            // One word per line, no custom wordlist if the field is empty
+                            let words = e.data.values.get("words")
+                                .map(|w| w.lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from).collect::<Vec<_>>())
+                                .filter(|w| !w.is_empty());
+                            if let (Some(mode), Some(language), Some(difficulty), Some(rounds), Some(max_players)) = (mode, lang, diff, rounds, max_players) {
                                 match urls::http_url_origin() {
                                     Ok(origin) => {
                                         let token = user.token; // Copies token
                                         to_owned![router, client, error]; // Clones states
                                         cx.spawn(async move {
-                                            let body = CreateGameBody { token, settings: GameSettings { mode, language, difficulty } };
+                                            let body = CreateGameBody { token, settings: GameSettings { mode, language, difficulty, rounds, max_players }, password, public, words };
                                             match client.post(format!("{origin}/api/game"))
                                                 .json(&body)
                                                 .send()
                                                 .await {
+                                                Ok(res) if !res.status().is_success() => {
+                                                    match res.text().await {
+                                                        Ok(reason) => error.set(Some(CreateGameError::Rejected(reason))),
+                                                        Err(e) => error.set(Some(e.into())),
+                                                    }
+                                                },
                                                 Ok(res) => {
                                                     match res.json::<GameCode>().await {
                                                         Ok(code) => {
@@ -65,7 +83,7 @@ pub fn CreateGame(cx: Scope) -> Element {
                                     Err(e) => error.set(Some(e.into())),
                                 }
                             } else {
-                                error!("failed to parse language from form");
+                                error!("failed to parse game settings from form");
                                 error.set(Some(CreateGameError::FormParseError));
                             }
                         },
@@ -95,15 +113,7 @@ pub fn CreateGame(cx: Scope) -> Element {
                             label {
                                 class: "flex items-center gap-2",
                                 MaterialIcon { name: "language", color: MaterialIconColor::Light, size: 42 },
-                                select {
-                                    class: "input p-1 w-full rounded",
-                                    required: true,
-                                    name: "language",
-                                    GameLanguage::all().iter().map(|l| {
-                                        let value = serde_json::to_string(&l).expect("failed to serialize language");
-                                        rsx!(option { value: "{value}", "{l}" })
-                                    })
-                                }
+                                LanguageSelect {}
                             }
                             label {
                                 class: "flex items-center gap-2",
@@ -119,6 +129,68 @@ pub fn CreateGame(cx: Scope) -> Element {
                                     })
                                 }
                             }
+                            label {
+                                class: "flex items-center gap-2",
+                                title: "Rounds per match in competitive mode",
+                                MaterialIcon { name: "repeat", color: MaterialIconColor::Light, size: 42 },
+                                input {
+                                    class: "input p-1 w-full rounded",
+                                    r#type: "number",
+                                    required: true,
+                                    name: "rounds",
+                                    min: 1,
+                                    max: 10,
+                                    value: "1",
+                                }
+                            }
+                            label {
+                                class: "flex items-center gap-2",
+                                title: "Maximum number of players",
+                                MaterialIcon { name: "group_add", color: MaterialIconColor::Light, size: 42 },
+                                input {
+                                    class: "input p-1 w-full rounded",
+                                    r#type: "number",
+                                    required: true,
+                                    name: "max_players",
+                                    min: 1,
+                                    max: 50,
+                                    value: "10",
+                                }
+                            }
+                            label {
+                                class: "flex items-center gap-2",
+                                title: "Only players with the password can join",
+                                MaterialIcon { name: "lock", color: MaterialIconColor::Light, size: 42 },
+                                input {
+                                    class: "input p-1 w-full rounded",
+                                    r#type: "password",
+                                    name: "password",
+                                    placeholder: "Password (optional)",
+                                }
+                            }
+                            label {
+                                class: "flex items-center gap-2",
+                                title: "List the game in the lobby browser",
+                                MaterialIcon { name: "public", color: MaterialIconColor::Light, size: 42 },
+                                input {
+                                    class: "input w-5 h-5 rounded",
+                                    r#type: "checkbox",
+                                    name: "public",
+                                    value: "true",
+                                }
+                                span { class: "font-light", "Public game" }
+                            }
+                            label {
+                                class: "flex items-center gap-2",
+                                title: "Words are only drawn from this list, one word per line",
+                                MaterialIcon { name: "edit_note", color: MaterialIconColor::Light, size: 42 },
+                                textarea {
+                                    class: "input p-1 w-full rounded",
+                                    name: "words",
+                                    rows: 3,
+                                    placeholder: "Custom words (optional)",
+                                }
+                            }
                         }
                     }
                 }
@@ -50,6 +50,7 @@ pub fn CreateUser(cx: Scope) -> Element {
                             class: "input p-1 w-full rounded",
                             placeholder: "Enter your name",
                             required: true,
+                            maxlength: 20,
                             name: "nickname",
                         }
                     }
@@ -4,7 +4,7 @@ use crate::{
 use dioxus::prelude::*;
 use dioxus_router::use_route;
 use fermi::use_read;
-use hangman_data::GameCode;
+use hangman_data::{GameCode, Role};
 use std::convert::Infallible;
 
 mod ongoing_game;
@@ -14,13 +14,27 @@ pub fn Game(cx: Scope) -> Element {
 
     let code = route.parse_segment::<GameCode>("code");
     let user = use_read(cx, USER);
+    let role = match route.query_param("role").as_deref() {
+        Some("spectator") => Role::Spectator,
+        _ => Role::Player,
+    };
+    // Every submitted password remounts the game to open a new connection with it
+    let password = use_state(cx, || (0u32, Option::<String>::None));
 
     match (code, user) {
         // Render game
-        (Some(Ok(code)), Ok(Some(user))) => cx.render(rsx!(OngoingGame {
-            code: code,
-            user: user
-        })),
+        (Some(Ok(code)), Ok(Some(user))) => {
+            let (attempt, current_password) = password.get();
+            let attempt = *attempt;
+            cx.render(rsx!(OngoingGame {
+                key: "{attempt}",
+                code: code,
+                user: user,
+                role: role,
+                password: current_password.clone(),
+                on_password: move |p| password.set((attempt + 1, Some(p))),
+            }))
+        }
 
         // Invalid game code
         (Some(Err(e)), _) => cx.render(rsx!(Error {
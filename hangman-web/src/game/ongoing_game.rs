@@ -1,5 +1,7 @@
 use crate::{
-    components::{CenterContainer, MaterialButton, RcError},
+    components::{
+        use_languages, CenterContainer, Form, MaterialButton, MaterialLinkButton, RcError, TopBar,
+    },
     game::{
         ongoing_game::{hangman::Hangman, scoreboard::Scoreboard, ws_logic::connect},
         GameCode,
@@ -14,8 +16,9 @@ use dioxus_router::use_router;
 use gloo_net::websocket::WebSocketError;
 use gloo_utils::errors::JsError;
 use hangman_data::{
-    ChatColor, ChatMessage, ClientMessage, CompetitiveState, Game, GameSettings, Score,
-    ServerMessage, ServerMessageInner, TeamState, User,
+    ChatColor, ChatMessage, ClientMessage, CompetitiveState, Game, GameSettings, JoinMessage, Role,
+    RoundResults, ServerError, ServerMessage, ServerMessageInner, TeamState, User, Warning,
+    WordOutcome, WordResult, MAX_TRIES,
 };
 use log::error;
 use std::{rc::Rc, time::Duration};
@@ -49,6 +52,22 @@ pub enum ConnectionError {
     GameNotFound,
     #[error("this game was closed")]
     GameClosed,
+    #[error("you were kicked from this game")]
+    Kicked,
+    #[error("you are banned from this game")]
+    Banned,
+    #[error("this game has reached its player limit")]
+    GameFull,
+    #[error("this game requires a password")]
+    PasswordRequired,
+    #[error("the password is wrong")]
+    WrongPassword,
+    #[error("you were disconnected for sending too many messages")]
+    RateLimited,
+    #[error("your nickname is too short, too long or contains a blocked word")]
+    InvalidNickname,
+    #[error("the game stopped because of an error on the server")]
+    GameCrashed,
 }
 
 impl ConnectionError {
@@ -62,7 +81,7 @@ pub enum ClientState {
     Loading,
     JoinedTeam(Game<TeamState>),
     JoinedCompetitive(Game<CompetitiveState>),
-    Results(Vec<Score>),
+    Results(RoundResults),
     /// Rc to make it cloneable
     Error(Rc<ConnectionError>),
 }
@@ -74,19 +93,36 @@ impl From<ServerMessage> for ClientState {
             ServerMessage::Competitive(ServerMessageInner::UpdateGame(game)) => {
                 Self::JoinedCompetitive(game)
             }
-            ServerMessage::Team(ServerMessageInner::Results(scores))
-            | ServerMessage::Competitive(ServerMessageInner::Results(scores)) => {
-                Self::Results(scores)
+            ServerMessage::Team(ServerMessageInner::Results(results))
+            | ServerMessage::Competitive(ServerMessageInner::Results(results)) => {
+                Self::Results(results)
+            }
+            ServerMessage::Team(ServerMessageInner::WordFinished(_))
+            | ServerMessage::Competitive(ServerMessageInner::WordFinished(_)) => {
+                unreachable!("finished words are handled by ws_read")
+            }
+            ServerMessage::Warning(_) | ServerMessage::Error(_) => {
+                unreachable!("warnings and errors are handled by ws_read")
             }
         }
     }
 }
 
 #[inline_props]
-pub fn OngoingGame<'a>(cx: Scope<'a>, code: GameCode, user: &'a User) -> Element<'a> {
+pub fn OngoingGame<'a>(
+    cx: Scope<'a>,
+    code: GameCode,
+    user: &'a User,
+    role: Role,
+    password: Option<String>,
+    on_password: EventHandler<'a, String>,
+) -> Element<'a> {
     let state = use_ref(cx, || ClientState::Loading);
+    let finished_word = use_ref(cx, || Option::<WordResult>::None);
+    let notice = use_ref(cx, || Option::<Notice>::None);
+    let spectating = *role == Role::Spectator;
 
-    let (ws_tx, ws_rx) = cx.use_hook(|| match urls::game_ws_url(code, user) {
+    let (ws_tx, ws_rx) = cx.use_hook(|| match urls::game_ws_url(code, user, *role) {
         Ok(url) => connect(state, url),
         Err(e) => {
             state.set(ClientState::Error(ConnectionError::UrlError(e).rc()));
@@ -94,12 +130,15 @@ pub fn OngoingGame<'a>(cx: Scope<'a>, code: GameCode, user: &'a User) -> Element
         }
     });
     let _ws_read: &Coroutine<()> = use_coroutine(cx, |_| {
-        to_owned![state];
-        ws_logic::ws_read(ws_rx.take(), state)
+        to_owned![state, finished_word, notice];
+        ws_logic::ws_read(ws_rx.take(), state, finished_word, notice)
     });
     let ws_write: &Coroutine<ClientMessage> = use_coroutine(cx, |rx| {
         to_owned![state];
-        ws_logic::ws_write(rx, ws_tx.take(), state)
+        let join = JoinMessage {
+            password: password.clone(),
+        };
+        ws_logic::ws_write(rx, ws_tx.take(), join, state)
     });
 
     state.with(|s| match s {
@@ -115,10 +154,27 @@ pub fn OngoingGame<'a>(cx: Scope<'a>, code: GameCode, user: &'a User) -> Element
                 }
             }
         )),
+        ClientState::Error(e)
+            if matches!(
+                **e,
+                ConnectionError::PasswordRequired | ConnectionError::WrongPassword
+            ) =>
+        {
+            cx.render(rsx!(PasswordPrompt {
+                wrong: matches!(**e, ConnectionError::WrongPassword),
+                onsubmit: move |p| on_password.call(p),
+            }))
+        }
         ClientState::Error(e) => {
             let title = match **e {
                 ConnectionError::GameNotFound => "Game not found",
                 ConnectionError::GameClosed => "The game was closed",
+                ConnectionError::Kicked => "You were kicked from the game",
+                ConnectionError::Banned => "You are banned from this game",
+                ConnectionError::GameFull => "This game is full",
+                ConnectionError::RateLimited => "You sent too many messages",
+                ConnectionError::InvalidNickname => "Your nickname is not allowed",
+                ConnectionError::GameCrashed => "The game crashed",
                 _ => "Connection error",
             };
             cx.render(rsx!(RcError {
@@ -128,14 +184,14 @@ pub fn OngoingGame<'a>(cx: Scope<'a>, code: GameCode, user: &'a User) -> Element
         }
         ClientState::JoinedTeam(Game { owner_hash, settings, players, state: None })
         | ClientState::JoinedCompetitive(Game { owner_hash, settings, players, state: None, .. }) => {
-            let is_owner = *owner_hash == user.token.hashed();
+            let is_owner = !spectating && *owner_hash == user.token.hashed();
             cx.render(rsx!(
-                Header { code: *code, settings: settings.clone(), countdown: None }
+                Header { code: *code, settings: settings.clone(), countdown: None, spectating: spectating }
                 div {
                     class: "h-full flex items-center",
                     div {
                         class: "grid waiting-container w-full items-center h-64",
-                        Players { players: players.clone() }
+                        Players { players: players.clone(), user: user, is_owner: is_owner, ws_write: ws_write }
                         div {
                             class: "flex flex-col gap-2",
                             style: "grid-area: spinner",
@@ -150,23 +206,30 @@ pub fn OngoingGame<'a>(cx: Scope<'a>, code: GameCode, user: &'a User) -> Element
                 Footer { show_next_round: is_owner, next_round_text: "Start Game", ws_write: ws_write }
             ))
         },
-        ClientState::JoinedTeam(Game { settings, state: Some(state), players, .. }) => cx.render(rsx!(
+        ClientState::JoinedTeam(Game { owner_hash, settings, state: Some(state), players }) => cx.render(rsx!(
             StartedGame {
                 code: *code,
                 settings: settings.clone(),
                 players: players.clone(),
+                user: user,
+                is_owner: !spectating && *owner_hash == user.token.hashed(),
+                spectating: spectating,
                 tries_used: state.tries_used,
                 chat: state.chat.clone(),
                 word: state.word.clone(),
                 show_next_round: state.round_finished,
                 ws_write: ws_write
             }
+            NoticeToast { notice: notice }
         )),
-        ClientState::JoinedCompetitive(Game { settings, state: Some(state), players, .. }) => cx.render(rsx!(
+        ClientState::JoinedCompetitive(Game { owner_hash, settings, state: Some(state), players }) => cx.render(rsx!(
             StartedGame {
                 code: *code,
                 settings: settings.clone(),
                 players: players.clone(),
+                user: user,
+                is_owner: !spectating && *owner_hash == user.token.hashed(),
+                spectating: spectating,
                 tries_used: state.tries_used,
                 chat: state.chat.clone(),
                 word: state.word.clone(),
@@ -174,17 +237,72 @@ pub fn OngoingGame<'a>(cx: Scope<'a>, code: GameCode, user: &'a User) -> Element
                 show_next_round: false,
                 ws_write: ws_write
             }
+            FinishedWord { finished_word: finished_word }
+            NoticeToast { notice: notice }
         )),
-        ClientState::Results(scores) => cx.render(rsx!(
-            Header { code: *code, countdown: None }
+        ClientState::Results(results) => cx.render(rsx!(
+            Header { code: *code, countdown: None, spectating: spectating }
             CenterContainer {
-                Scoreboard { scores: scores.clone() }
+                Scoreboard { results: results.clone() }
+            }
+            Footer {
+                show_next_round: !spectating,
+                next_round_text: if results.match_finished() { "New Match →" } else { "Next Round →" },
+                ws_write: ws_write
             }
-            Footer { show_next_round: true, ws_write: ws_write }
         )),
     })
 }
 
+#[inline_props]
+fn PasswordPrompt<'a>(
+    cx: Scope<'a>,
+    wrong: bool,
+    onsubmit: EventHandler<'a, String>,
+) -> Element<'a> {
+    cx.render(rsx!(
+        CenterContainer {
+            Form {
+                onsubmit: move |e: FormEvent| {
+                    if let Some(password) = e.data.values.get("password") {
+                        onsubmit.call(password.to_string());
+                    } else {
+                        error!("failed to parse password form field");
+                    }
+                },
+                TopBar {
+                    MaterialLinkButton { name: "arrow_back", to: "/" }
+                    span {
+                        class: "font-light",
+                        "Private game"
+                    }
+                    MaterialButton { name: "done" }
+                }
+                div {
+                    class: "p-6 flex flex-col gap-1",
+                    label {
+                        class: "flex items-center gap-2",
+                        MaterialIcon { name: "lock", color: MaterialIconColor::Light, size: 42 }
+                        input {
+                            class: "input p-1 w-full rounded",
+                            r#type: "password",
+                            placeholder: "Enter the password",
+                            required: true,
+                            name: "password",
+                        }
+                    }
+                    wrong.then(|| rsx!(
+                        p {
+                            class: "text-red-400 font-light text-center",
+                            "Wrong password"
+                        }
+                    ))
+                }
+            }
+        }
+    ))
+}
+
 // TODO: WTF is this prop mess
 #[inline_props]
 fn StartedGame<'a>(
@@ -192,6 +310,9 @@ fn StartedGame<'a>(
     code: GameCode,
     settings: GameSettings,
     players: Vec<String>,
+    user: &'a User,
+    is_owner: bool,
+    spectating: bool,
     tries_used: u32,
     chat: Vec<ChatMessage>,
     word: String,
@@ -199,44 +320,149 @@ fn StartedGame<'a>(
     show_next_round: bool,
     ws_write: &'a Coroutine<ClientMessage>,
 ) -> Element<'a> {
+    // Spectators of competitive games don't get a word, every player has their own
+    let watching_players = *spectating && word.is_empty();
+    let title = if watching_players {
+        "THE PLAYERS ARE GUESSING"
+    } else {
+        "GUESS THE WORD"
+    };
+
     cx.render(rsx!(
-        Header { code: *code, settings: settings.clone(), countdown: *countdown }
+        Header { code: *code, settings: settings.clone(), countdown: *countdown, spectating: *spectating }
         div {
             class: "h-full flex items-center",
             div {
                 class: "grid game-container gap-y-2 w-full",
 
                 // Players
-                Players { players: players.clone() }
+                Players { players: players.clone(), user: user, is_owner: *is_owner, ws_write: ws_write }
 
                 // Word
                 h1 {
                     class: "text-xl font-light text-center",
                     style: "grid-area: title",
-                    "GUESS THE WORD"
-                }
-                pre {
-                    class: "text-6xl font-mono tracking-[.25em] mr-[-.25em] text-center px-2",
-                    style: "grid-area: word",
-                    "{word}"
+                    "{title}"
                 }
+                (!watching_players).then(|| rsx!(
+                    pre {
+                        class: "text-6xl font-mono tracking-[.25em] mr-[-.25em] text-center px-2",
+                        style: "grid-area: word",
+                        "{word}"
+                    }
+                ))
 
                 Chat {
                     chat: chat.clone(),
                     disabled: *show_next_round,
+                    read_only: *spectating,
                     ws_write: ws_write
                 }
 
                 // Hangman
-                Hangman { tries_used: *tries_used }
+                (!watching_players).then(|| rsx!(Hangman { tries_used: *tries_used }))
+            }
+        }
+        Footer { show_next_round: *show_next_round && !*spectating, ws_write: ws_write }
+    ))
+}
+
+/// Briefly shows the word a player just finished
+#[inline_props]
+fn FinishedWord<'a>(cx: Scope<'a>, finished_word: &'a UseRef<Option<WordResult>>) -> Element<'a> {
+    let current = finished_word.read().clone();
+
+    use_future(cx, (&current,), |(current,)| {
+        to_owned![finished_word];
+        async move {
+            if current.is_some() {
+                gloo_timers::future::sleep(Duration::from_millis(2500)).await;
+                finished_word.set(None);
+            }
+        }
+    });
+
+    let WordResult {
+        word,
+        outcome,
+        tries_used,
+    } = current?;
+    let (title, color_class) = match outcome {
+        WordOutcome::Solved => ("Solved!", "bg-green-900/30"),
+        WordOutcome::Failed => ("No tries left!", "bg-red-900/30"),
+        WordOutcome::TimeUp => ("Time is up!", "bg-red-900/30"),
+    };
+
+    cx.render(rsx!(
+        div {
+            class: "absolute inset-0 flex justify-center items-center pointer-events-none",
+            div {
+                class: "bg-zinc-800 rounded-xl shadow-lg overflow-hidden text-center",
+                p {
+                    class: "{color_class} text-xl font-light px-8 py-2",
+                    "{title}"
+                }
+                pre {
+                    class: "text-5xl font-mono tracking-[.25em] mr-[-.25em] px-8 py-4",
+                    "{word}"
+                }
+                p {
+                    class: "font-light pb-2",
+                    "{tries_used} of {MAX_TRIES} tries used"
+                }
             }
         }
-        Footer { show_next_round: *show_next_round, ws_write: ws_write }
     ))
 }
 
+/// Warning or error the server sent while the game continues
+#[derive(Clone, Debug, PartialEq)]
+pub enum Notice {
+    Warning(Warning),
+    Error(ServerError),
+}
+
+/// Briefly shows the last notice sent by the server
 #[inline_props]
-fn Players(cx: Scope, players: Vec<String>) -> Element {
+fn NoticeToast<'a>(cx: Scope<'a>, notice: &'a UseRef<Option<Notice>>) -> Element<'a> {
+    let current = notice.read().clone();
+
+    use_future(cx, (&current,), |(current,)| {
+        to_owned![notice];
+        async move {
+            if current.is_some() {
+                gloo_timers::future::sleep(Duration::from_millis(2000)).await;
+                notice.set(None);
+            }
+        }
+    });
+
+    let text = match current? {
+        Notice::Warning(Warning::RateLimited { .. }) => "Slow down! Your last message was ignored",
+        Notice::Error(ServerError::WordGeneration { .. }) => {
+            "Failed to get a new word, please try again"
+        }
+    };
+
+    cx.render(rsx!(
+        div {
+            class: "absolute top-14 inset-x-0 flex justify-center pointer-events-none",
+            p {
+                class: "bg-red-900/70 rounded-lg shadow-lg font-light px-4 py-1",
+                "{text}"
+            }
+        }
+    ))
+}
+
+#[inline_props]
+fn Players<'a>(
+    cx: Scope<'a>,
+    players: Vec<String>,
+    user: &'a User,
+    is_owner: bool,
+    ws_write: &'a Coroutine<ClientMessage>,
+) -> Element<'a> {
     let router = use_router(cx);
 
     cx.render(rsx!(
@@ -251,7 +477,28 @@ fn Players(cx: Scope, players: Vec<String>) -> Element {
                         li {
                             class: "flex items-center gap-1",
                             MaterialIcon { name: "account_circle", color: MaterialIconColor::Light, size: 30 }
-                            "{p}"
+                            span { class: "grow", "{p}" }
+                            // Owner-only controls
+                            (*is_owner && *p != user.nickname).then(|| rsx!(
+                                button {
+                                    class: "material-button px-1",
+                                    title: "Make game owner",
+                                    onclick: move |_| ws_write.send(ClientMessage::TransferOwnership(p.clone())),
+                                    MaterialIcon { name: "star", color: MaterialIconColor::Light, size: 20 }
+                                }
+                                button {
+                                    class: "material-button px-1",
+                                    title: "Kick",
+                                    onclick: move |_| ws_write.send(ClientMessage::Kick(p.clone())),
+                                    MaterialIcon { name: "person_remove", color: MaterialIconColor::Light, size: 20 }
+                                }
+                                button {
+                                    class: "material-button px-1 hover:bg-red-700/70",
+                                    title: "Ban",
+                                    onclick: move |_| ws_write.send(ClientMessage::Ban(p.clone())),
+                                    MaterialIcon { name: "block", color: MaterialIconColor::Light, size: 20 }
+                                }
+                            ))
                         }
                     ))
             }
@@ -270,18 +517,23 @@ struct HeaderProps {
     settings: Option<GameSettings>,
     #[props(!optional)]
     countdown: Option<chrono::DateTime<Utc>>,
+    #[props(default)]
+    spectating: bool,
 }
 
 fn Header(cx: Scope<HeaderProps>) -> Element {
     let router = use_router(cx);
+    let languages = use_languages(cx);
 
-    let on_copy = move |_| {
+    // Copies the link to join the game, or to watch it when `spectate` is set
+    let copy_link = move |spectate: bool| {
         // TODO: Provide feedback to the user
         // Fixme: Doesn't work in other browsers than FF
         match web_sys::window().and_then(|w| w.navigator().clipboard()) {
             Some(c) => {
                 let mut url = router.current_location().url.clone();
                 url.set_path(&format!("/game/{}", cx.props.code));
+                url.set_query(spectate.then_some("role=spectator"));
                 cx.spawn(async move {
                     if let Err(e) =
                         wasm_bindgen_futures::JsFuture::from(c.write_text(url.as_str())).await
@@ -320,11 +572,16 @@ fn Header(cx: Scope<HeaderProps>) -> Element {
     });
 
     let lang_button = cx.props.settings.as_ref().map(|s| {
+        // The code is shown until the names are loaded
+        let language = languages
+            .iter()
+            .find(|l| l.code == s.language)
+            .map_or_else(|| s.language.to_string(), |l| l.name.clone());
         cx.render(rsx!(
             button {
                 class: "material-button gap-1 bg-zinc-700",
                 MaterialIcon { name: "language", color: MaterialIconColor::Light, size: 35 }
-                span { "{s.language}" }
+                span { "{language}" }
             }
         ))
     });
@@ -335,7 +592,8 @@ fn Header(cx: Scope<HeaderProps>) -> Element {
             div {
                 class: "flex items-center gap-1 p-1",
                 span { class: "font-mono text-xl", "{cx.props.code}" }
-                MaterialButton { name: "content_copy", onclick: on_copy }
+                MaterialButton { name: "content_copy", onclick: move |_| copy_link(false) }
+                MaterialButton { name: "visibility", onclick: move |_| copy_link(true) }
             }
             div {
                 class: "font-mono text-2xl p-1",
@@ -343,6 +601,13 @@ fn Header(cx: Scope<HeaderProps>) -> Element {
             }
             div {
                 class: "flex items-center gap-1",
+                cx.props.spectating.then(|| rsx!(
+                    span {
+                        class: "flex items-center gap-1 px-2 py-1 rounded-full bg-zinc-700 font-light",
+                        MaterialIcon { name: "visibility", color: MaterialIconColor::Light, size: 30 }
+                        "Spectating"
+                    }
+                ))
                 lang_button
             }
         }
@@ -377,6 +642,8 @@ fn Chat<'a>(
     cx: Scope<'a>,
     chat: Vec<ChatMessage>,
     disabled: bool,
+    /// Hides the input, e.g. for spectators
+    read_only: bool,
     ws_write: &'a Coroutine<ClientMessage>,
 ) -> Element<'a> {
     let value = use_state(cx, String::new);
@@ -389,12 +656,18 @@ fn Chat<'a>(
         }
     };
 
+    let list_rounding = if *read_only {
+        "rounded-lg"
+    } else {
+        "rounded-t-lg"
+    };
+
     cx.render(rsx!(
         div {
             class: "flex flex-col gap-0",
             style: "grid-area: chat",
             ul {
-                class: "bg-zinc-800 rounded-t-lg overflow-y-auto font-light flex flex-col-reverse h-64",
+                class: "bg-zinc-800 {list_rounding} overflow-y-auto font-light flex flex-col-reverse h-64",
                 chat.iter()
                     .rev()
                     .map(|ChatMessage { from, content, color }| {
@@ -413,21 +686,23 @@ fn Chat<'a>(
                         })
                     })
             }
-            form {
-                class: "w-full",
-                prevent_default: "onsubmit",
-                onsubmit: on_letter_submit,
-                input {
-                    class: "input w-full px-2 py-1 rounded-b-lg font-light",
-                    r#type: "text",
-                    maxlength: 1,
-                    name: "letter",
-                    placeholder: "Guess something...",
-                    disabled: *disabled,
-                    value: "{value}",
-                    oninput: move |e| value.set(e.data.value.to_string()),
+            (!*read_only).then(|| rsx!(
+                form {
+                    class: "w-full",
+                    prevent_default: "onsubmit",
+                    onsubmit: on_letter_submit,
+                    input {
+                        class: "input w-full px-2 py-1 rounded-b-lg font-light",
+                        r#type: "text",
+                        maxlength: 1,
+                        name: "letter",
+                        placeholder: "Guess something...",
+                        disabled: *disabled,
+                        value: "{value}",
+                        oninput: move |e| value.set(e.data.value.to_string()),
+                    }
                 }
-            }
+            ))
         }
     ))
 }
@@ -1,13 +1,58 @@
 use crate::components::TopBar;
 use dioxus::prelude::*;
-use hangman_data::Score;
+use hangman_data::{RoundResults, Score, WordOutcome};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tab {
+    Round,
+    Total,
+}
 
 #[inline_props]
-pub fn Scoreboard(cx: Scope, scores: Vec<Score>) -> Element {
-    let order = if scores.len() < 3 {
-        vec![0, 1]
+pub fn Scoreboard(cx: Scope, results: RoundResults) -> Element {
+    let tab = use_state(cx, || {
+        if results.match_finished() {
+            Tab::Total
+        } else {
+            Tab::Round
+        }
+    });
+
+    let title = if results.rounds <= 1 {
+        "Results".to_string()
+    } else if results.match_finished() {
+        "Final Results".to_string()
     } else {
-        vec![1, 0, 2]
+        format!("Round {} of {}", results.round, results.rounds)
+    };
+    let tabs = (results.rounds > 1).then(|| {
+        let tab_class = |t: Tab| {
+            if *tab.get() == t {
+                "ring-zinc-500"
+            } else {
+                "bg-transparent shadow-none ring-zinc-700"
+            }
+        };
+        let (round_class, total_class) = (tab_class(Tab::Round), tab_class(Tab::Total));
+        rsx!(
+            div {
+                class: "flex justify-center gap-2 pt-4",
+                button {
+                    class: "base-button {round_class}",
+                    onclick: move |_| tab.set(Tab::Round),
+                    "Round"
+                }
+                button {
+                    class: "base-button {total_class}",
+                    onclick: move |_| tab.set(Tab::Total),
+                    "Total"
+                }
+            }
+        )
+    });
+    let scores = match tab.get() {
+        Tab::Round => &results.round_scores,
+        Tab::Total => &results.total_scores,
     };
 
     cx.render(rsx!(
@@ -16,22 +61,40 @@ pub fn Scoreboard(cx: Scope, scores: Vec<Score>) -> Element {
             TopBar {
                 p {
                     class: "mx-auto text-xl font-light",
-                    "Results"
+                    "{title}"
                 }
             }
+            tabs
+            Standings { scores: scores }
+        }
+    ))
+}
+
+#[inline_props]
+fn Standings<'a>(cx: Scope<'a>, scores: &'a Vec<Score>) -> Element<'a> {
+    let order = if scores.len() < 3 {
+        vec![0, 1]
+    } else {
+        vec![1, 0, 2]
+    };
+
+    cx.render(rsx!(
+        div {
+            class: "p-8",
             div {
-                class: "p-8",
-                div {
-                    class: "flex gap-8 items-end",
-                    order.iter().map(|i| rsx!(scores.get(*i).map(|score| rsx!(Podium { score: score }))))
-                }
-                div {
-                    class: "flex justify-evenly",
-                    scores.get(3).map(|score| rsx!(ShortPodium { score: score }))
-                    scores.get(4).map(|score| rsx!(ShortPodium { score: score }))
-                }
+                class: "flex gap-8 items-end",
+                order.iter().map(|i| rsx!(scores.get(*i).map(|score| rsx!(Podium { score: score }))))
+            }
+            div {
+                class: "flex justify-evenly",
+                scores.get(3).map(|score| rsx!(ShortPodium { score: score }))
+                scores.get(4).map(|score| rsx!(ShortPodium { score: score }))
             }
         }
+        div {
+            class: "border-t border-zinc-700 p-4 flex flex-col gap-2 max-h-64 overflow-y-auto",
+            scores.iter().map(|score| rsx!(WordRecap { score: score }))
+        }
     ))
 }
 
@@ -58,7 +121,7 @@ fn Podium<'a>(cx: Scope<'a>, score: &'a Score) -> Element<'a> {
                     br {}
                     span {
                         class: "text-xl",
-                        if score.score == 1 { "word" } else { "words" }
+                        if score.score == 1 { "point" } else { "points" }
                     }
                 }
                 p {
@@ -83,3 +146,41 @@ fn ShortPodium<'a>(cx: Scope<'a>, score: &'a Score) -> Element<'a> {
         }
     ))
 }
+
+#[inline_props]
+fn WordRecap<'a>(cx: Scope<'a>, score: &'a Score) -> Element<'a> {
+    let breakdown = &score.breakdown;
+    let solve_secs = score.solve_time_ms / 1000;
+    let solve_time = format!("{:0>2}:{:0>2}", solve_secs / 60, solve_secs % 60);
+
+    cx.render(rsx!(
+        div {
+            div {
+                class: "flex justify-between items-baseline gap-4",
+                p {
+                    class: "font-light",
+                    "{score.nickname}"
+                }
+                p {
+                    class: "text-xs font-light text-zinc-400",
+                    "{breakdown.words_solved} solved · {breakdown.base} base · {breakdown.length} length · "
+                    "{breakdown.tries} tries · {breakdown.speed} speed · {solve_time}"
+                }
+            }
+            ul {
+                class: "flex flex-wrap gap-1",
+                score.words.iter().map(|w| {
+                    let color_class = match w.outcome {
+                        WordOutcome::Solved => "bg-green-900/30",
+                        WordOutcome::Failed | WordOutcome::TimeUp => "bg-red-900/30",
+                    };
+                    rsx!(li {
+                        class: "{color_class} rounded px-2 font-mono",
+                        title: "{w.tries_used} tries used",
+                        "{w.word}"
+                    })
+                })
+            }
+        }
+    ))
+}
@@ -1,12 +1,15 @@
-use crate::game::ongoing_game::{ClientState, ConnectionError};
+use crate::game::ongoing_game::{ClientState, ConnectionError, Notice};
 use dioxus::prelude::*;
 use futures::{
     stream::{SplitSink, SplitStream},
     SinkExt, StreamExt,
 };
 use gloo_net::websocket::{futures::WebSocket, Message, WebSocketError};
-use hangman_data::{ClientMessage, ServerMessage};
-use log::debug;
+use hangman_data::{
+    ClientMessage, CloseCode, JoinMessage, ServerMessage, ServerMessageInner, WordResult,
+};
+use log::{debug, error};
+use serde::Serialize;
 
 pub fn connect(
     state: &UseRef<ClientState>,
@@ -28,11 +31,28 @@ pub fn connect(
     }
 }
 
-pub async fn ws_read(ws_rx: Option<SplitStream<WebSocket>>, state: UseRef<ClientState>) {
+pub async fn ws_read(
+    ws_rx: Option<SplitStream<WebSocket>>,
+    state: UseRef<ClientState>,
+    finished_word: UseRef<Option<WordResult>>,
+    notice: UseRef<Option<Notice>>,
+) {
     if let Some(mut ws_read) = ws_rx {
         while let Some(msg) = ws_read.next().await {
             match msg {
                 Ok(Message::Text(s)) => match serde_json::from_str::<ServerMessage>(&s) {
+                    Ok(ServerMessage::Team(ServerMessageInner::WordFinished(result)))
+                    | Ok(ServerMessage::Competitive(ServerMessageInner::WordFinished(result))) => {
+                        finished_word.set(Some(result))
+                    }
+                    Ok(ServerMessage::Warning(w)) => {
+                        debug!("received warning: {w:?}");
+                        notice.set(Some(Notice::Warning(w)))
+                    }
+                    Ok(ServerMessage::Error(e)) => {
+                        error!("received error: {e:?}");
+                        notice.set(Some(Notice::Error(e)))
+                    }
                     Ok(msg) => state.set(msg.into()),
                     Err(e) => state.set(ClientState::Error(
                         ConnectionError::DeserializeError(e).rc(),
@@ -43,17 +63,21 @@ pub async fn ws_read(ws_rx: Option<SplitStream<WebSocket>>, state: UseRef<Client
                         ConnectionError::DeserializeWrongDataTypeError.rc(),
                     ));
                 }
-                Err(WebSocketError::ConnectionClose(gloo_net::websocket::events::CloseEvent {
-                    code: 4000,
-                    ..
-                })) => {
-                    state.set(ClientState::Error(ConnectionError::GameNotFound.rc()));
-                }
-                Err(WebSocketError::ConnectionClose(gloo_net::websocket::events::CloseEvent {
-                    code: 4001,
-                    ..
-                })) => {
-                    state.set(ClientState::Error(ConnectionError::GameClosed.rc()));
+                Err(WebSocketError::ConnectionClose(e)) => {
+                    let error = match CloseCode::try_from(e.code) {
+                        Ok(CloseCode::GameNotFound) => ConnectionError::GameNotFound,
+                        Ok(CloseCode::GameClosed) => ConnectionError::GameClosed,
+                        Ok(CloseCode::Kicked) => ConnectionError::Kicked,
+                        Ok(CloseCode::Banned) => ConnectionError::Banned,
+                        Ok(CloseCode::GameFull) => ConnectionError::GameFull,
+                        Ok(CloseCode::PasswordRequired) => ConnectionError::PasswordRequired,
+                        Ok(CloseCode::WrongPassword) => ConnectionError::WrongPassword,
+                        Ok(CloseCode::RateLimited) => ConnectionError::RateLimited,
+                        Ok(CloseCode::InvalidNickname) => ConnectionError::InvalidNickname,
+                        Ok(CloseCode::GameCrashed) => ConnectionError::GameCrashed,
+                        Err(_) => ConnectionError::WsError(WebSocketError::ConnectionClose(e)),
+                    };
+                    state.set(ClientState::Error(error.rc()));
                 }
                 Err(e) => {
                     state.set(ClientState::Error(ConnectionError::WsError(e).rc()));
@@ -63,23 +87,34 @@ pub async fn ws_read(ws_rx: Option<SplitStream<WebSocket>>, state: UseRef<Client
     }
 }
 
+/// Sends the join message and then all client messages
 pub async fn ws_write(
     mut rx: UnboundedReceiver<ClientMessage>,
     ws_tx: Option<SplitSink<WebSocket, Message>>,
+    join: JoinMessage,
     state: UseRef<ClientState>,
 ) {
     if let Some(mut ws_write) = ws_tx {
+        send(&mut ws_write, &join, &state).await;
         while let Some(msg) = rx.next().await {
-            match serde_json::to_string(&msg) {
-                Ok(s) => match ws_write.send(Message::Text(s)).await {
-                    Err(WebSocketError::MessageSendError(e)) => {
-                        state.set(ClientState::Error(ConnectionError::SendError(e).rc()))
-                    }
-                    Err(e) => state.set(ClientState::Error(ConnectionError::WsError(e).rc())),
-                    Ok(_) => {}
-                },
-                Err(e) => state.set(ClientState::Error(ConnectionError::SerializeError(e).rc())),
-            }
+            send(&mut ws_write, &msg, &state).await;
         }
     }
 }
+
+async fn send(
+    ws_write: &mut SplitSink<WebSocket, Message>,
+    msg: &impl Serialize,
+    state: &UseRef<ClientState>,
+) {
+    match serde_json::to_string(msg) {
+        Ok(s) => match ws_write.send(Message::Text(s)).await {
+            Err(WebSocketError::MessageSendError(e)) => {
+                state.set(ClientState::Error(ConnectionError::SendError(e).rc()))
+            }
+            Err(e) => state.set(ClientState::Error(ConnectionError::WsError(e).rc())),
+            Ok(_) => {}
+        },
+        Err(e) => state.set(ClientState::Error(ConnectionError::SerializeError(e).rc())),
+    }
+}
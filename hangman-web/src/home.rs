@@ -31,6 +31,7 @@ pub fn Home(cx: Scope) -> Element {
                     class: "flex flex-col gap-4",
                     LinkButton { to: "/create", "Create Game" }
                     JoinButton {}
+                    LinkButton { to: "/browse", "Browse Games" }
                 }
             }
         }
@@ -7,6 +7,7 @@ use dioxus_router::{Route, Router};
 use fermi::use_init_atom_root;
 use std::convert::Infallible;
 
+mod browse_games;
 mod components;
 mod create_game;
 mod create_user;
@@ -35,6 +36,7 @@ fn App(cx: Scope) -> Element {
             MaterialIconStylesheet {}
             Route { to: "/", home::Home {} }
             Route { to: "/create", create_game::CreateGame {} }
+            Route { to: "/browse", browse_games::BrowseGames {} }
             Route { to: "/game/:code", game::Game {} }
             Route { to: "", NotFound {} }
         }
@@ -1,4 +1,4 @@
-use hangman_data::{GameCode, User};
+use hangman_data::{GameCode, Role, User};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -9,16 +9,20 @@ pub enum UrlError {
     JsError,
 }
 
-pub fn game_ws_url(code: &GameCode, user: &User) -> Result<String, UrlError> {
+pub fn game_ws_url(code: &GameCode, user: &User, role: Role) -> Result<String, UrlError> {
     match web_sys::window()
         .map(|w| w.location())
         .map(|l| (l.protocol(), l.host()))
     {
         Some((Ok(protocol), Ok(host))) => {
-            let query = form_urlencoded::Serializer::new(String::new())
+            let mut query = form_urlencoded::Serializer::new(String::new());
+            query
                 .append_pair("nickname", &user.nickname)
-                .append_pair("token", &format!("{}", user.token))
-                .finish();
+                .append_pair("token", &format!("{}", user.token));
+            if role == Role::Spectator {
+                query.append_pair("role", "spectator");
+            }
+            let query = query.finish();
             let protocol = if protocol == "https:" { "wss:" } else { "ws:" };
             Ok(format!("{protocol}//{host}/api/game/{code}/ws?{query}"))
         }
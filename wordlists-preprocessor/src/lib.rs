@@ -0,0 +1,380 @@
+//! Filtering of Leipzig Corpora Collection word frequency lists
+//!
+//! Every line of a `*-words.txt` file has the form `id<TAB>word<TAB>occurrences`,
+//! sorted by occurrences, most common first.
+//!
+//! The output is a wordlist in the format of [`hangman_data::wordlist`],
+//! the most common word has rank 1.
+
+use hangman_data::wordlist::{self, CaseRules, HEADER};
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    num::ParseIntError,
+    str::FromStr,
+};
+use thiserror::Error;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PreprocessError {
+    #[error("line {line}: invalid number: {source}")]
+    InvalidNumber { line: usize, source: ParseIntError },
+}
+
+/// Which words are kept
+#[derive(Clone, Debug)]
+pub struct Filter {
+    /// Words that occur less often are skipped
+    pub min_occurrences: u32,
+    /// The most common tokens are mostly punctuation, words containing them are skipped
+    ///
+    /// Tokens are matched against the segments of a word, so `the` doesn't skip `mother`.
+    pub special_tokens: u32,
+    /// Minimum number of letters
+    pub min_len: usize,
+    /// Maximum number of letters
+    pub max_len: usize,
+    /// Words that differ only in case are merged, the most common form is kept
+    pub case: CaseRules,
+    /// Folded letters words can consist of, all letters are allowed if this is empty
+    pub alphabet: HashSet<String>,
+    /// Words that are capitalized more often than this share are treated as proper nouns and skipped
+    pub max_capitalized: f64,
+    /// Folded words that are always skipped
+    pub stopwords: HashSet<String>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            min_occurrences: 100,
+            special_tokens: 100,
+            min_len: 1,
+            max_len: usize::MAX,
+            case: CaseRules::default(),
+            alphabet: HashSet::new(),
+            max_capitalized: 0.9,
+            stopwords: HashSet::new(),
+        }
+    }
+}
+
+impl Filter {
+    /// Only allows words consisting of these letters, `case` has to be set before
+    pub fn set_alphabet(&mut self, alphabet: &str) {
+        self.alphabet = alphabet
+            .graphemes(true)
+            .map(|l| self.case.fold(l))
+            .collect();
+    }
+
+    /// Skips the words of a list with one word per line, `case` has to be set before
+    pub fn set_stopwords(&mut self, stopwords: &str) {
+        self.stopwords = stopwords
+            .lines()
+            .map(str::trim)
+            .filter(|w| !w.is_empty())
+            .map(|w| self.case.fold(w))
+            .collect();
+    }
+
+    fn is_in_alphabet(&self, word: &str) -> bool {
+        self.alphabet.is_empty()
+            || word
+                .graphemes(true)
+                .all(|l| self.alphabet.contains(&self.case.fold(l)))
+    }
+
+    /// Checks that don't need the occurrences, `letters` and `folded` are of `word`
+    fn skips(&self, word: &str, letters: usize, folded: &str) -> bool {
+        !(self.min_len..=self.max_len).contains(&letters)
+            || word.contains(|c: char| c.is_numeric())
+            || !self.is_in_alphabet(word)
+            || self.stopwords.contains(folded)
+    }
+}
+
+/// All forms of a word that only differ in case
+struct Forms<'a> {
+    /// Most common form
+    word: &'a str,
+    word_occurrences: u32,
+    letters: usize,
+    occurrences: u64,
+    capitalized: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Word<'a> {
+    pub word: &'a str,
+    /// Occurrences of all forms of the word, unknown for wordlists of the old format
+    pub occurrences: Option<u64>,
+    /// Number of graphemes
+    pub letters: usize,
+}
+
+/// Result of filtering a wordlist
+#[derive(Debug, Default, PartialEq)]
+pub struct Preprocessed<'a> {
+    /// Kept words, most common first
+    pub words: Vec<Word<'a>>,
+    /// Number of words that were skipped
+    pub skipped: usize,
+}
+
+/// Filters a Leipzig word frequency list
+///
+/// Lines without exactly three fields are skipped, an id or count that isn't a number is an error.
+pub fn preprocess<'a>(
+    input: &'a str,
+    filter: &Filter,
+) -> Result<Preprocessed<'a>, PreprocessError> {
+    let mut special_tokens = vec![];
+    let mut output = Preprocessed::default();
+    let mut forms: Vec<Forms> = vec![];
+    let mut folded_forms = HashMap::new();
+
+    for (i, line) in input.lines().enumerate() {
+        let [id, word, occurrences] = line.split_ascii_whitespace().collect::<Vec<_>>()[..] else {
+            continue;
+        };
+        let parse = |s| {
+            u32::from_str(s).map_err(|source| PreprocessError::InvalidNumber {
+                line: i + 1,
+                source,
+            })
+        };
+        let id = parse(id)?;
+        let occurrences = parse(occurrences)?;
+
+        if id <= filter.special_tokens {
+            special_tokens.push(word);
+            continue;
+        }
+        let len = word.graphemes(true).count();
+        let folded = filter.case.fold(word);
+        if filter.skips(word, len, &folded)
+            || word
+                .split_word_bounds()
+                .any(|segment| special_tokens.contains(&segment))
+        {
+            output.skipped += 1;
+            continue;
+        }
+
+        let capitalized = if word.chars().next().is_some_and(char::is_uppercase) {
+            occurrences as u64
+        } else {
+            0
+        };
+        match folded_forms.entry(folded) {
+            Entry::Occupied(e) => {
+                let f: &mut Forms = &mut forms[*e.get()];
+                if occurrences > f.word_occurrences {
+                    f.word = word;
+                    f.word_occurrences = occurrences;
+                    f.letters = len;
+                }
+                f.occurrences += occurrences as u64;
+                f.capitalized += capitalized;
+                output.skipped += 1;
+            }
+            Entry::Vacant(e) => {
+                e.insert(forms.len());
+                forms.push(Forms {
+                    word,
+                    word_occurrences: occurrences,
+                    letters: len,
+                    occurrences: occurrences as u64,
+                    capitalized,
+                });
+            }
+        }
+    }
+
+    for f in forms {
+        let proper_noun = f.capitalized as f64 > filter.max_capitalized * f.occurrences as f64;
+        if f.occurrences >= filter.min_occurrences as u64 && !proper_noun {
+            output.words.push(Word {
+                word: f.word,
+                occurrences: Some(f.occurrences),
+                letters: f.letters,
+            });
+        } else {
+            output.skipped += 1;
+        }
+    }
+    // Merged forms can change the order
+    output
+        .words
+        .sort_by_key(|w| std::cmp::Reverse(w.occurrences));
+    Ok(output)
+}
+
+/// Writes the words in the preprocessed format, with the category of each word if it has one
+pub fn to_tsv<'a>(words: &[Word], category: impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut tsv = format!("{HEADER}\n");
+    for (i, w) in words.iter().enumerate() {
+        let entry = wordlist::Entry {
+            word: w.word,
+            occurrences: w.occurrences,
+            category: category(w.word),
+        };
+        tsv.push_str(&entry.to_line(i + 1, w.letters));
+    }
+    tsv
+}
+
+/// Filters a wordlist of the old format, one word per line and the most common first
+///
+/// The occurrences of the words are unknown, so rare words and proper nouns can't be skipped.
+/// Of words that only differ in case, the first one is kept.
+pub fn convert_legacy<'a>(input: &'a str, filter: &Filter) -> Preprocessed<'a> {
+    let mut output = Preprocessed::default();
+    let mut folded_words = HashSet::new();
+    for word in input.lines().map(str::trim).filter(|w| !w.is_empty()) {
+        let letters = word.graphemes(true).count();
+        let folded = filter.case.fold(word);
+        if filter.skips(word, letters, &folded) || !folded_words.insert(folded) {
+            output.skipped += 1;
+            continue;
+        }
+        output.words.push(Word {
+            word,
+            occurrences: None,
+            letters,
+        });
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "1\t.\t5000\n\
+                          2\t,\t4000\n\
+                          3\tthe\t3000\n\
+                          4\thouse\t900\n\
+                          5\tdon't\t800\n\
+                          6\t2018\t700\n\
+                          7\tlighthouse\t600\n\
+                          8\tA.M.\t500\n\
+                          9\tcafé\t400\n\
+                          10\trare\t5\n\
+                          malformed line\n";
+
+    fn words<'a>(output: &Preprocessed<'a>) -> Vec<&'a str> {
+        output.words.iter().map(|w| w.word).collect()
+    }
+
+    fn filter(special_tokens: u32) -> Filter {
+        Filter {
+            min_occurrences: 10,
+            special_tokens,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn skips_special_and_rare_words() {
+        let output = preprocess(SAMPLE, &filter(2)).unwrap();
+        assert_eq!(
+            words(&output),
+            vec!["the", "house", "don't", "lighthouse", "café"]
+        );
+        assert_eq!(output.skipped, 3);
+    }
+
+    #[test]
+    fn special_tokens_match_whole_segments() {
+        let input = "1\tthe\t5000\n\
+                     2\t-\t4000\n\
+                     3\tmother\t300\n\
+                     4\tweather\t200\n\
+                     5\te-mail\t100\n";
+        let output = preprocess(input, &filter(2)).unwrap();
+        assert_eq!(words(&output), vec!["mother", "weather"]);
+    }
+
+    #[test]
+    fn length_limits() {
+        let filter = Filter {
+            min_len: 4,
+            max_len: 5,
+            ..filter(3)
+        };
+        let output = preprocess(SAMPLE, &filter).unwrap();
+        assert_eq!(words(&output), vec!["house", "don't", "café"]);
+    }
+
+    #[test]
+    fn merges_case_and_skips_proper_nouns() {
+        let input = "1\tthe\t300\n\
+                     2\tThe\t200\n\
+                     3\tI\t150\n\
+                     4\tParis\t100\n\
+                     5\tparis\t5\n\
+                     6\tHouse\t50\n\
+                     7\thouse\t60\n";
+        let output = preprocess(input, &filter(0)).unwrap();
+        assert_eq!(words(&output), vec!["the", "house"]);
+        assert_eq!(output.skipped, 5);
+
+        let filter = Filter {
+            max_capitalized: 1.0,
+            ..filter(0)
+        };
+        let output = preprocess(input, &filter).unwrap();
+        assert_eq!(words(&output), vec!["the", "I", "house", "Paris"]);
+    }
+
+    #[test]
+    fn alphabet_and_stopwords() {
+        let input = "1\tcafé\t300\n\
+                     2\tнет\t200\n\
+                     3\tnaïve\t150\n\
+                     4\tça\t100\n\
+                     5\tà\t50\n";
+        let mut filter = filter(0);
+        filter.set_alphabet("abcdefghijklmnopqrstuvwxyzàçé");
+        filter.set_stopwords("à\n\n");
+        let output = preprocess(input, &filter).unwrap();
+        assert_eq!(words(&output), vec!["café", "ça"]);
+        assert_eq!(output.skipped, 3);
+    }
+
+    #[test]
+    fn tsv() {
+        let output = preprocess(SAMPLE, &filter(2)).unwrap();
+        let tsv = to_tsv(&output.words[..2], |w| (w == "house").then_some("noun"));
+        assert_eq!(
+            tsv,
+            format!("{HEADER}\n1\tthe\t3000\t3\t\n2\thouse\t900\t5\tnoun\n")
+        );
+    }
+
+    #[test]
+    fn legacy() {
+        let mut filter = filter(0);
+        filter.set_alphabet("abcdefghijklmnopqrstuvwxyzé");
+        let output = convert_legacy("the\nThe\ncafé\n\nI\ni\n2018\nнет\n", &filter);
+        assert_eq!(words(&output), vec!["the", "café", "I"]);
+        assert_eq!(output.skipped, 4);
+        assert_eq!(
+            to_tsv(&output.words[..2], |_| None),
+            format!("{HEADER}\n1\tthe\t\t3\t\n2\tcafé\t\t4\t\n")
+        );
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        assert_eq!(
+            preprocess("1\tthe\tmany", &filter(0)).unwrap_err(),
+            PreprocessError::InvalidNumber {
+                line: 1,
+                source: u32::from_str("many").unwrap_err(),
+            }
+        );
+    }
+}
@@ -1,46 +1,200 @@
-use std::{error::Error, str::FromStr};
-use tokio::{fs, io::AsyncWriteExt};
+use clap::Parser;
+use hangman_data::wordlist::CaseRules;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    error::Error,
+    path::{Path, PathBuf},
+};
+use tokio::fs;
+use wordlists_preprocessor::{convert_legacy, preprocess, to_tsv, Filter};
+
+/// Turns Leipzig word frequency lists into wordlists for the hangman server
+///
+/// Wordlists of the old `*.pre.txt` format are filtered too,
+/// as far as that is possible without occurrences.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Wordlist files or directories containing them
+    #[arg(default_value = "wordlists")]
+    input: Vec<PathBuf>,
+    /// Directory to write the preprocessed wordlists to, defaults to the directory of each input
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Only files in input directories with this suffix are processed
+    #[arg(long, default_value = "-words.txt")]
+    suffix: String,
+    /// Words that occur less often are skipped
+    #[arg(long, default_value_t = 100)]
+    min_occurrences: u32,
+    /// Number of most common tokens that are treated as special characters
+    #[arg(long, default_value_t = 100)]
+    special_tokens: u32,
+    /// Minimum number of letters of a word
+    #[arg(long, default_value_t = 1)]
+    min_len: usize,
+    /// Maximum number of letters of a word
+    #[arg(long, default_value_t = usize::MAX, hide_default_value = true)]
+    max_len: usize,
+    /// Language manifest of the server, the alphabet, case rules and share of capitalized words
+    /// of each wordlist are taken from it
+    #[arg(short, long)]
+    manifest: Option<PathBuf>,
+    /// Letters words can consist of, overrides the alphabets of the manifest
+    #[arg(long)]
+    alphabet: Option<String>,
+    /// Words that are capitalized more often than this share are skipped as proper nouns,
+    /// used for languages without `max_capitalized` in the manifest
+    #[arg(long, default_value_t = 0.9)]
+    max_capitalized: f64,
+    /// File with words that are skipped, one per line
+    #[arg(long)]
+    stopwords: Option<PathBuf>,
+    /// File with a word and its category per line, separated by a tab
+    #[arg(long)]
+    categories: Option<PathBuf>,
+    /// Only print how many words would be kept, don't write any files
+    #[arg(long)]
+    dry_run: bool,
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let mut dir = fs::read_dir("wordlists").await?;
-    while let Some(wordlist_path) = dir.next_entry().await? {
-        if wordlist_path
-            .file_name()
-            .into_string()
-            .expect("failed to convert os string to string")
-            .ends_with("-words.txt")
-        {
-            println!("processing {:?}", wordlist_path.file_name());
-
-            let mut path = wordlist_path.path();
-            path.set_extension("pre.txt");
-            let mut file = fs::File::create(path).await?;
-
-            let mut special_chars = vec![];
-
-            for line in fs::read_to_string(wordlist_path.path()).await?.lines() {
-                let split_line: Vec<&str> = line.split_ascii_whitespace().collect();
-                if split_line.len() == 3 {
-                    let id = u32::from_str(split_line.first().expect("failed to parse id"))?;
-                    let word = split_line.get(1).expect("failed to parse word");
-                    let occurrences =
-                        u32::from_str(split_line.get(2).expect("failed to parse occurrences"))?;
-
-                    if id <= 100 {
-                        special_chars.push(word.to_string());
-                    } else if occurrences >= 100
-                        && !word.contains(|c: char| c.is_numeric())
-                        && special_chars.iter().all(|s| !word.contains(s))
-                    {
-                        file.write_all(word.as_bytes()).await?;
-                        file.write_all(b"\n").await?;
-                    }
+    let args = Args::parse();
+    let manifest = match &args.manifest {
+        Some(path) => toml::from_str(&fs::read_to_string(path).await?)?,
+        None => Manifest::default(),
+    };
+    let stopwords = match &args.stopwords {
+        Some(path) => fs::read_to_string(path).await?,
+        None => String::new(),
+    };
+    let categories = match &args.categories {
+        Some(path) => fs::read_to_string(path).await?,
+        None => String::new(),
+    };
+    let categories: HashMap<&str, &str> = categories
+        .lines()
+        .filter_map(|l| l.split_once('\t'))
+        .map(|(word, category)| (word.trim(), category.trim()))
+        .collect();
+
+    let mut wordlists = vec![];
+    for input in &args.input {
+        if fs::metadata(input).await?.is_dir() {
+            let mut dir = fs::read_dir(input).await?;
+            while let Some(entry) = dir.next_entry().await? {
+                if entry.file_name().to_string_lossy().ends_with(&args.suffix) {
+                    wordlists.push(entry.path());
                 }
             }
+        } else {
+            wordlists.push(input.clone());
+        }
+    }
+    wordlists.sort();
 
-            println!("processed {:?}", wordlist_path.file_name());
+    for path in wordlists {
+        println!("processing {path:?}");
+        let output_path = output_path(&path, args.output.as_deref());
+        let language = output_path.file_name().and_then(|name| {
+            let name = wordlist_name(name.to_str()?);
+            manifest
+                .language
+                .iter()
+                .find(|l| wordlist_name(&l.file) == name)
+        });
+        if args.manifest.is_some() && language.is_none() {
+            println!("{output_path:?} is not listed in the manifest, all letters are allowed");
         }
+        let mut filter = Filter {
+            min_occurrences: args.min_occurrences,
+            special_tokens: args.special_tokens,
+            min_len: args.min_len,
+            max_len: args.max_len,
+            case: language.map(|l| l.case).unwrap_or_default(),
+            max_capitalized: language
+                .and_then(|l| l.max_capitalized)
+                .unwrap_or(args.max_capitalized),
+            ..Default::default()
+        };
+        if let Some(alphabet) = args
+            .alphabet
+            .as_deref()
+            .or(language.map(|l| l.alphabet.as_str()))
+        {
+            filter.set_alphabet(alphabet);
+        }
+        filter.set_stopwords(&stopwords);
+
+        let input = fs::read_to_string(&path).await?;
+        let output = if path.to_string_lossy().ends_with(LEGACY_EXTENSION) {
+            convert_legacy(&input, &filter)
+        } else {
+            preprocess(&input, &filter).map_err(|e| format!("{path:?}: {e}"))?
+        };
+        if args.dry_run {
+            println!(
+                "would write {} words to {output_path:?}, skipped {}",
+                output.words.len(),
+                output.skipped
+            );
+            continue;
+        }
+
+        let content = to_tsv(&output.words, |w| categories.get(w).copied());
+        fs::write(&output_path, content).await?;
+        println!(
+            "wrote {} words to {output_path:?}, skipped {}",
+            output.words.len(),
+            output.skipped
+        );
     }
     Ok(())
 }
+
+/// The parts of the server's `languages.toml` that matter for preprocessing
+#[derive(Debug, Default, Deserialize)]
+struct Manifest {
+    language: Vec<Language>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Language {
+    /// Name of the preprocessed wordlist, older wordlists end with `.pre.txt`
+    file: String,
+    #[serde(default)]
+    alphabet: String,
+    #[serde(default)]
+    case: CaseRules,
+    /// 1 keeps all words, e.g. for German, which capitalizes all nouns
+    max_capitalized: Option<f64>,
+}
+
+/// Extension of preprocessed wordlists in the old format
+const LEGACY_EXTENSION: &str = ".pre.txt";
+
+/// `*-words.txt` and `*-words.pre.txt` become `*-words.pre.tsv`
+fn output_path(input: &Path, output_dir: Option<&Path>) -> PathBuf {
+    let name = input
+        .file_name()
+        .expect("input is a file")
+        .to_string_lossy();
+    let name = name
+        .strip_suffix(LEGACY_EXTENSION)
+        .or_else(|| name.strip_suffix(".txt"))
+        .unwrap_or(&name);
+    let file = format!("{name}.pre.tsv");
+    match output_dir {
+        Some(dir) => dir.join(file),
+        None => input.with_file_name(file),
+    }
+}
+
+/// Name of a preprocessed wordlist without the extension of the old or the current format
+fn wordlist_name(file: &str) -> &str {
+    file.strip_suffix(".pre.tsv")
+        .or_else(|| file.strip_suffix(LEGACY_EXTENSION))
+        .unwrap_or(file)
+}